@@ -2,8 +2,21 @@ use cosmwasm_std::{to_json_binary, Addr, Binary, StdResult, Uint64, WasmMsg};
 
 use crate::{
     msg::{
-        ClientMsg, CreationParams, ExecuteMsg, FlagParams, KeyValue, PartitionSelector,
-        Relationship, RelationshipUpdates, TagUpdate, TagUpdates, UpdateParams,
+        BatchCreationParams,
+        BatchMode,
+        ClientMsg,
+        CreationParams,
+        ExecuteMsg,
+        ExpirySettings,
+        FlagParams,
+        KeyValue,
+        PartitionSelector,
+        RegistrationParams,
+        Relationship,
+        RelationshipUpdates,
+        TagUpdate,
+        TagUpdates,
+        UpdateParams,
     },
     state::GroupID,
 };
@@ -32,8 +45,13 @@ impl Table {
         use_lifecycle_hooks: bool,
         partition: PartitionSelector,
         admin: Option<Addr>,
+        salt: Option<Binary>,
         groups: Option<Vec<GroupID>>,
         tags: Option<Vec<String>>,
+        tag_options: Option<Vec<TagUpdate>>,
+        values: Option<Vec<KeyValue>>,
+        relationships: Option<Vec<Relationship>>,
+        expiry: Option<ExpirySettings>,
     ) -> StdResult<WasmMsg> {
         Ok(WasmMsg::Execute {
             contract_addr: self.table_addr.clone().into(),
@@ -43,9 +61,46 @@ impl Table {
                 label: Some(label),
                 use_lifecycle_hooks: Some(use_lifecycle_hooks),
                 admin,
+                salt,
                 partition,
                 groups,
                 tags,
+                tag_options,
+                values,
+                relationships,
+                expiry,
+            })))?,
+            funds: vec![],
+        })
+    }
+
+    pub fn create_many(
+        &self,
+        items: Vec<CreationParams>,
+        mode: Option<BatchMode>,
+    ) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.table_addr.clone().into(),
+            msg: to_json_binary(&ExecuteMsg::Client(ClientMsg::CreateMany(
+                BatchCreationParams { items, mode },
+            )))?,
+            funds: vec![],
+        })
+    }
+
+    pub fn register(
+        &self,
+        use_lifecycle_hooks: bool,
+        partition: PartitionSelector,
+        groups: Option<Vec<GroupID>>,
+    ) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.table_addr.clone().into(),
+            msg: to_json_binary(&ExecuteMsg::Client(ClientMsg::Register(RegistrationParams {
+                contract: self.client_addr.clone(),
+                use_lifecycle_hooks: Some(use_lifecycle_hooks),
+                partition,
+                groups,
             })))?,
             funds: vec![],
         })
@@ -66,6 +121,26 @@ impl Table {
                 values,
                 tags,
                 relationships,
+                expiry: None,
+            })))?,
+            funds: vec![],
+        })
+    }
+
+    pub fn set_expiry(
+        &self,
+        initiator: &Addr,
+        expiry: ExpirySettings,
+    ) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.table_addr.clone().into(),
+            msg: to_json_binary(&ExecuteMsg::Client(ClientMsg::Update(UpdateParams {
+                contract: self.client_addr.clone(),
+                initiator: initiator.clone(),
+                values: None,
+                tags: None,
+                relationships: None,
+                expiry: Some(expiry),
             })))?,
             funds: vec![],
         })
@@ -81,6 +156,16 @@ impl Table {
         })
     }
 
+    pub fn archive(&self) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.table_addr.clone().into(),
+            msg: to_json_binary(&ExecuteMsg::Client(ClientMsg::Archive(
+                self.client_addr.clone(),
+            )))?,
+            funds: vec![],
+        })
+    }
+
     pub fn tag(&self, initiator: &Addr, tags: Vec<TagUpdate>) -> StdResult<WasmMsg> {
         self.update(
             initiator,
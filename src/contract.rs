@@ -1,4 +1,5 @@
 use crate::context::Context;
+use crate::ensure::ensure_valid_index_constraints;
 use crate::error::ContractError;
 use crate::execute;
 use crate::models::ReplyJob;
@@ -10,7 +11,7 @@ use crate::query;
 use crate::state::{
     self, load_reply_job, CustomIndexMap, CONFIG_STR_CASE_SENSITIVE, CONFIG_STR_MAX_LEN,
     CONTRACT_ID_2_ADDR, CONTRACT_TAGS, CONTRACT_USES_LIFECYCLE_HOOKS, IX_TAG, PARTITION_TAG_COUNTS,
-    REL_ADDR_2_ID, REL_ID_2_ADDR, X,
+    REL_ADDR_2_ID, REL_ID_2_ADDR, REPLY_JOBS, X,
 };
 use crate::util::{build_index_storage_key, pad};
 use cosmwasm_std::{
@@ -29,6 +30,9 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    for params in msg.indices.iter().flatten() {
+        ensure_valid_index_constraints(params)?;
+    }
     let ctx = Context { deps, env, info };
     set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     state::initialize(ctx, msg)?;
@@ -48,8 +52,13 @@ pub fn execute(
         // this "table" OR by accounts with owner auth:
         ExecuteMsg::Client(msg) => match msg {
             ClientMsg::Create(params) => execute::client::create::on_execute(ctx, params),
+            ClientMsg::CreateMany(params) => execute::client::create_many::on_execute(ctx, params),
+            ClientMsg::Register(params) => execute::client::register::on_execute(ctx, params),
+            ClientMsg::Refresh(params) => execute::client::refresh::on_execute(ctx, params),
             ClientMsg::Update(params) => execute::client::update::on_execute(ctx, params),
             ClientMsg::Delete(addr) => execute::client::delete::on_execute(ctx, addr),
+            ClientMsg::Archive(addr) => execute::client::archive::on_execute(ctx, addr),
+            ClientMsg::Expire(params) => execute::client::expire::on_execute(ctx, params),
             ClientMsg::Flag(params) => execute::client::flag::on_execute(ctx, params),
         },
         // Admin functions - require "owner" auth:
@@ -57,6 +66,19 @@ pub fn execute(
             AdminMsg::SetOwner(owner) => execute::admin::set_owner::on_execute(ctx, owner),
             AdminMsg::UpdateInfo(info) => execute::admin::update_info::on_execute(ctx, info),
             AdminMsg::Unsuspend(addr) => execute::admin::unsuspend::on_execute(ctx, addr),
+            AdminMsg::ClearReplyJobs(params) => {
+                execute::admin::clear_reply_jobs::on_execute(ctx, params)
+            },
+            AdminMsg::MigrateContracts(params) => {
+                execute::admin::migrate_contracts::on_execute(ctx, params)
+            },
+            AdminMsg::Restore(addr) => execute::admin::restore::on_execute(ctx, addr),
+            AdminMsg::UpdateContractAdmin(params) => {
+                execute::admin::update_contract_admin::on_update(ctx, params)
+            },
+            AdminMsg::ClearContractAdmin(params) => {
+                execute::admin::update_contract_admin::on_clear(ctx, params)
+            },
 
             // Config operations
             AdminMsg::UpdateConfig(config) => {
@@ -66,7 +88,17 @@ pub fn execute(
 
             // Index operations
             AdminMsg::CreateIndex(params) => execute::admin::create_index::on_execute(ctx, params),
-            AdminMsg::DeleteIndex(name) => execute::admin::delete_index::on_execute(ctx, name),
+            AdminMsg::CreateCompoundIndex(params) => {
+                execute::admin::create_compound_index::on_execute(ctx, params)
+            },
+            AdminMsg::DeleteIndex(params) => execute::admin::delete_index::on_execute(ctx, params),
+            AdminMsg::DeleteCompoundIndex(params) => {
+                execute::admin::delete_compound_index::on_execute(ctx, params)
+            },
+            AdminMsg::Reindex(params) => execute::admin::reindex::on_execute(ctx, params),
+            AdminMsg::IndexManagedContracts(params) => {
+                execute::admin::index_managed_contracts::on_execute(ctx, params)
+            },
 
             // Partition operations
             AdminMsg::CreatePartition(params) => {
@@ -91,33 +123,58 @@ pub fn reply(
     reply: Reply,
 ) -> Result<Response, ContractError> {
     let job = load_reply_job(deps.storage, reply.id)?;
+
+    // Jobs are only needed until their reply is processed
+    REPLY_JOBS.remove(deps.storage, reply.id);
+
     return Ok(match job {
-        ReplyJob::Create { params, initiator } => {
-            execute::client::create::on_reply(deps, env, reply, params, initiator)
-        },
+        ReplyJob::Create {
+            params,
+            initiator,
+            batch_id,
+        } => execute::client::create::on_reply(deps, env, reply, params, initiator, batch_id),
     }?);
 }
 
 #[entry_point]
 pub fn query(
     deps: Deps,
-    _env: Env,
+    env: Env,
     msg: QueryMsg,
 ) -> Result<Binary, ContractError> {
     let result = match msg {
         // Paginate top-level data structures related to the table.
         QueryMsg::Table(msg) => match msg {
             TableQueryMsg::Indices(params) => to_json_binary(&query::table::indices(deps, params)?),
+            TableQueryMsg::CompoundIndices(params) => {
+                to_json_binary(&query::table::compound_indices(deps, params)?)
+            },
+            TableQueryMsg::ReindexStatus(index_name) => {
+                to_json_binary(&query::table::reindex_status(deps, index_name)?)
+            },
+            TableQueryMsg::ManagedIndexingStatus {} => {
+                to_json_binary(&query::table::managed_indexing_status(deps)?)
+            },
+            TableQueryMsg::Aggregates(params) => {
+                to_json_binary(&query::table::aggregates(deps, params)?)
+            },
+            TableQueryMsg::Facets(params) => to_json_binary(&query::table::facets(deps, params)?),
             TableQueryMsg::Partitions(params) => {
                 to_json_binary(&query::table::partitions(deps, params)?)
             },
             TableQueryMsg::Tags(params) => to_json_binary(&query::table::tags(deps, params)?),
             TableQueryMsg::Groups(params) => to_json_binary(&query::table::groups(deps, params)?),
+            TableQueryMsg::PredictAddress(params) => {
+                to_json_binary(&query::table::predict_address(deps, env, params)?)
+            },
+            TableQueryMsg::ReplyJobs(params) => {
+                to_json_binary(&query::table::reply_jobs(deps, params)?)
+            },
         },
         // Paginate collections of contracts by various means.
         QueryMsg::Contracts(msg) => match msg {
             ContractsQueryMsg::Range(params) => {
-                to_json_binary(&query::contracts::range(deps, params)?)
+                to_json_binary(&query::contracts::range(deps, env, params)?)
             },
             ContractsQueryMsg::WithTag(params) => {
                 to_json_binary(&query::contracts::with_tag(deps, params)?)
@@ -131,6 +188,12 @@ pub fn query(
             ContractsQueryMsg::RelatedTo(params) => {
                 to_json_binary(&query::contracts::related_to(deps, params)?)
             },
+            ContractsQueryMsg::Drifted(params) => {
+                to_json_binary(&query::contracts::drifted(deps, env, params)?)
+            },
+            ContractsQueryMsg::Archived(params) => {
+                to_json_binary(&query::contracts::archived(deps, params)?)
+            },
         },
         // Paginate relationshps, groups, & tags associated with a given contract.
         QueryMsg::Contract(msg) => match msg {
@@ -144,6 +207,9 @@ pub fn query(
             ContractQueryMsg::IsRelatedTo(params) => {
                 to_json_binary(&query::contract::is_related_to(deps, params)?)
             },
+            ContractQueryMsg::Values(params) => {
+                to_json_binary(&query::contract::values(deps, params)?)
+            },
         },
     }?;
     Ok(result)
@@ -259,6 +325,11 @@ pub fn migrate(
                 }
             }
         },
+        MigrateMsg::ClearStaleReplyJobs {} => {
+            // Jobs are now removed when replied to, so any that remain are
+            // left over from earlier versions.
+            REPLY_JOBS.clear(deps.storage);
+        },
     }
     Ok(Response::default())
 }
@@ -2,7 +2,9 @@ use cosmwasm_std::Storage;
 
 use crate::{
     error::ContractError,
+    msg::{IndexCreationParams, IndexType},
     state::{CODE_ID_ALLOWLIST, CONFIG_CODE_ID_ALLOWLIST_ENABLED},
+    util::{parse, parse_index_value},
 };
 
 pub fn ensure_authorized_code_id(
@@ -18,3 +20,76 @@ pub fn ensure_authorized_code_id(
     }
     Ok(())
 }
+
+pub fn ensure_valid_index_constraints(params: &IndexCreationParams) -> Result<(), ContractError> {
+    if let Some(constraints) = &params.constraints {
+        let is_sized = matches!(params.index_type, IndexType::String | IndexType::Binary);
+        for bound in [&constraints.min, &constraints.max].into_iter().flatten() {
+            if is_sized {
+                parse::<u32>(bound.clone())?;
+            } else {
+                parse_index_value(&params.index_type, bound.clone())?;
+            }
+        }
+        if constraints.pattern.is_some() && params.index_type != IndexType::String {
+            return Err(ContractError::ValidationError {
+                reason: format!("pattern constraint on non-string index {}", params.name),
+            });
+        }
+    }
+    if params.multi.unwrap_or(false) && params.unique.is_some() {
+        return Err(ContractError::ValidationError {
+            reason: format!("set-valued index {} cannot be unique", params.name),
+        });
+    }
+    if params.facets.unwrap_or(false)
+        && !matches!(
+            params.index_type,
+            IndexType::Bool | IndexType::Uint8 | IndexType::Uint16 | IndexType::String
+        )
+    {
+        return Err(ContractError::ValidationError {
+            reason: format!("facets not supported for index {}", params.name),
+        });
+    }
+    if params.index_type == IndexType::Geo {
+        if params.constraints.is_some() {
+            return Err(ContractError::ValidationError {
+                reason: format!("constraints on geo index {}", params.name),
+            });
+        }
+        if params.multi.unwrap_or(false) {
+            return Err(ContractError::ValidationError {
+                reason: format!("geo index {} cannot be set-valued", params.name),
+            });
+        }
+    }
+    if params.text.unwrap_or(false) {
+        if params.index_type != IndexType::String {
+            return Err(ContractError::ValidationError {
+                reason: format!("text search on non-string index {}", params.name),
+            });
+        }
+        if params.multi.unwrap_or(false) {
+            return Err(ContractError::ValidationError {
+                reason: format!(
+                    "set-valued index {} cannot support text search",
+                    params.name
+                ),
+            });
+        }
+    }
+    if let Some(strings) = &params.strings {
+        if params.index_type != IndexType::String {
+            return Err(ContractError::ValidationError {
+                reason: format!("string settings on non-string index {}", params.name),
+            });
+        }
+        if strings.max_len == Some(0) {
+            return Err(ContractError::ValidationError {
+                reason: format!("max length of index {} must be positive", params.name),
+            });
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
-use crate::state::ContractID;
+use crate::{msg::IndexType, state::ContractID};
 
 #[derive(Debug, Error)]
 pub enum ContractError {
@@ -38,6 +38,28 @@ pub enum ContractError {
     #[error("ContractSuspended: contract {contract_id:?} has been flagged and suspended")]
     ContractSuspended { contract_id: ContractID },
 
+    #[error("UnknownIndex: index {index:?} does not exist")]
+    UnknownIndex { index: String },
+
+    #[error("IndexDeleting: index {index:?} is being deleted")]
+    IndexDeleting { index: String },
+
+    #[error("IndexTypeMismatch: index {index:?} has type {expected:?}, not {actual:?}")]
+    IndexTypeMismatch {
+        index: String,
+        expected: IndexType,
+        actual: IndexType,
+    },
+
+    #[error("IndexConstraintViolation: index {index:?}: {reason:?}")]
+    IndexConstraintViolation { index: String, reason: String },
+
+    #[error("UniqueIndexConflict: value of index {index:?} is used by contract {contract_id:?}")]
+    UniqueIndexConflict {
+        index: String,
+        contract_id: ContractID,
+    },
+
     #[error("InvalidCursor: {reason:?}")]
     InvalidCursor { reason: String },
 
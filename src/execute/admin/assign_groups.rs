@@ -3,6 +3,7 @@ use cosmwasm_std::Response;
 use crate::{
     context::Context,
     error::ContractError,
+    execute::client::update::set_group_aggregate_entries,
     msg::GroupUpdates,
     state::{append_group, ensure_allowed_by_acl, load_contract_id, remove_from_group},
 };
@@ -21,6 +22,7 @@ pub fn on_execute(
     if let Some(group_ids) = updates.remove {
         for group_id in group_ids.iter() {
             remove_from_group(deps.storage, *group_id, contract_id)?;
+            set_group_aggregate_entries(deps.storage, *group_id, contract_id, false)?;
         }
     }
 
@@ -28,6 +30,7 @@ pub fn on_execute(
     if let Some(group_ids) = updates.add {
         for group_id in group_ids.iter() {
             append_group(deps.storage, *group_id, contract_id)?;
+            set_group_aggregate_entries(deps.storage, *group_id, contract_id, true)?;
         }
     }
 
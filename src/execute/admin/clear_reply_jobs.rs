@@ -0,0 +1,39 @@
+use cosmwasm_std::{attr, Order, Response};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    msg::{ReplyJobClearParams, ReplyJobStatus},
+    state::{ensure_allowed_by_acl, FAILED_REPLY_JOBS},
+};
+
+/// Remove reply jobs with the given status, oldest first.
+pub fn on_execute(
+    ctx: Context,
+    params: ReplyJobClearParams,
+) -> Result<Response, ContractError> {
+    let action = "clear_reply_jobs";
+    let Context { deps, info, .. } = ctx;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/clear-reply-jobs")?;
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 500) as usize;
+
+    let job_ids: Vec<u64> = match params.status {
+        ReplyJobStatus::Failed => FAILED_REPLY_JOBS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    for job_id in job_ids.iter() {
+        match params.status {
+            ReplyJobStatus::Failed => FAILED_REPLY_JOBS.remove(deps.storage, *job_id),
+        }
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", action),
+        attr("count", job_ids.len().to_string()),
+    ]))
+}
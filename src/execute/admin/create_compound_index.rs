@@ -0,0 +1,23 @@
+use cosmwasm_std::Response;
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    msg::CompoundIndexCreationParams,
+    state::{create_compound_index, ensure_allowed_by_acl},
+};
+
+/// Create an index over several existing custom indices. Entries are added for
+/// each contract the next time any of the components are updated.
+pub fn on_execute(
+    ctx: Context,
+    params: CompoundIndexCreationParams,
+) -> Result<Response, ContractError> {
+    let action = "create_compound_index";
+    let Context { deps, info, .. } = ctx;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/create-compound-index")?;
+    create_compound_index(deps.storage, params)?;
+
+    Ok(Response::new().add_attribute("action", action))
+}
@@ -2,6 +2,7 @@ use cosmwasm_std::Response;
 
 use crate::{
     context::Context,
+    ensure::ensure_valid_index_constraints,
     error::ContractError,
     msg::IndexCreationParams,
     state::{create_index, ensure_allowed_by_acl},
@@ -15,6 +16,7 @@ pub fn on_execute(
     let Context { deps, info, .. } = ctx;
 
     ensure_allowed_by_acl(&deps, &info.sender, "/table/create-index")?;
+    ensure_valid_index_constraints(&params)?;
     create_index(deps.storage, params)?;
 
     Ok(Response::new().add_attribute("action", action))
@@ -0,0 +1,92 @@
+use cosmwasm_std::{attr, to_json_binary, Order, Response, Uint64};
+use cw_storage_plus::Map;
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    msg::{IndexDeletionParams, IndexDeletionProgress},
+    state::{
+        ensure_allowed_by_acl, CompoundIndexMap, ContractID, PartitionID, COMPOUND_INDEX_METADATA,
+        INDEX_COMPOUNDS, REINDEX_PROGRESS, VALUES_COMPOUND,
+    },
+    util::build_index_storage_key,
+};
+
+/// Delete a compound index in batches. The first call unlinks the index from
+/// its components, after which it's no longer updated. Each call then removes
+/// a page of index entries along with their stored values, until none remain
+/// and the index metadata itself is removed.
+pub fn on_execute(
+    ctx: Context,
+    params: IndexDeletionParams,
+) -> Result<Response, ContractError> {
+    let action = "delete_compound_index";
+    let Context { deps, info, env } = ctx;
+    let index_name = params.name;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/delete-compound-index")?;
+
+    let mut meta = COMPOUND_INDEX_METADATA
+        .may_load(deps.storage, index_name.clone())?
+        .ok_or_else(|| ContractError::UnknownIndex {
+            index: index_name.clone(),
+        })?;
+
+    let mut progress = match meta.deletion.take() {
+        Some(progress) => progress,
+        None => {
+            REINDEX_PROGRESS.remove(deps.storage, &index_name);
+            for component in meta.components.iter() {
+                INDEX_COMPOUNDS.remove(deps.storage, (component, &index_name));
+            }
+            IndexDeletionProgress {
+                cursor: None,
+                removed: Uint64::zero(),
+                started_at: env.block.time,
+            }
+        },
+    };
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 500) as usize;
+
+    // Removed entries are gone from the next page, so each call starts over
+    // from the beginning of the index.
+    let index_storage_key = build_index_storage_key(&index_name);
+    let index: CompoundIndexMap = Map::new(&index_storage_key);
+    let keys: Vec<(PartitionID, Vec<u8>)> = index
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (partition, compound_key) in keys.iter() {
+        // Compound keys end with the big-endian contract ID
+        let n = compound_key.len();
+        if n >= 8 {
+            let mut id_bytes = [0u8; 8];
+            id_bytes.copy_from_slice(&compound_key[n - 8..]);
+            let contract_id = ContractID::from_be_bytes(id_bytes);
+            VALUES_COMPOUND.remove(deps.storage, (contract_id, &index_name));
+        }
+        index.remove(deps.storage, (*partition, compound_key.clone()));
+        progress.removed += Uint64::one();
+    }
+
+    // Stored values of contracts without an entry, such as archived ones, are
+    // dropped lazily, as each contract is next moved, restored or deleted.
+    let is_complete = keys.len() < limit;
+    if is_complete {
+        COMPOUND_INDEX_METADATA.remove(deps.storage, index_name.clone());
+    } else {
+        meta.deletion = Some(progress.clone());
+        COMPOUND_INDEX_METADATA.save(deps.storage, index_name.clone(), &meta)?;
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", action),
+            attr("index", index_name),
+            attr("removed", progress.removed.to_string()),
+            attr("is_complete", is_complete.to_string()),
+        ])
+        .set_data(to_json_binary(&progress)?))
+}
@@ -1,73 +1,262 @@
-use cosmwasm_std::Response;
-use cw_storage_plus::Map;
+use cosmwasm_std::{attr, to_json_binary, Order, Response, Storage, Timestamp, Uint64};
 
 use crate::{
     context::Context,
     error::ContractError,
-    msg::IndexType,
-    state::{ensure_allowed_by_acl, ContractID, PartitionID, INDEX_METADATA},
-    util::build_index_storage_key,
+    execute::client::update::{remove_index_value, set_custom_index_entry},
+    msg::{IndexDeletionParams, IndexDeletionProgress},
+    state::{
+        ensure_allowed_by_acl, ContractID, CONTRACT_ID_COUNTER, CONTRACT_INDEX_TYPES,
+        CONTRACT_METADATA, INDEX_COMPOUNDS, INDEX_METADATA, REINDEX_PROGRESS, REQUIRED_INDICES,
+    },
 };
 
+/// Delete a custom index in batches. The first call marks the index as being
+/// deleted, after which writes to it are refused. Each call then removes the
+/// index entries and stored values of a page of contract IDs, including those
+/// of archived contracts and values left with a mismatched type, until every
+/// ID has been scanned and the index metadata itself is removed.
 pub fn on_execute(
     ctx: Context,
-    index_name: String,
+    params: IndexDeletionParams,
 ) -> Result<Response, ContractError> {
-    let Context { deps, info, .. } = ctx;
+    let action = "delete_index";
+    let Context { deps, info, env } = ctx;
+    let index_name = params.name;
 
     ensure_allowed_by_acl(&deps, &info.sender, "/table/delete-index")?;
 
-    if let Some(meta) = INDEX_METADATA.may_load(deps.storage, index_name.clone())? {
-        INDEX_METADATA.remove(deps.storage, index_name.clone());
-
-        let map_name = &build_index_storage_key(&index_name);
-
-        match meta.index_type {
-            IndexType::String => {
-                let map: Map<(PartitionID, &String, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Bool => {
-                let map: Map<(PartitionID, u8, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Timestamp => {
-                let map: Map<(PartitionID, u64, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Int32 => {
-                let map: Map<(PartitionID, i32, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Uint8 => {
-                let map: Map<(PartitionID, u8, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Uint16 => {
-                let map: Map<(PartitionID, u8, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Uint32 => {
-                let map: Map<(PartitionID, u32, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Uint64 => {
-                let map: Map<(PartitionID, u64, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Uint128 => {
-                let map: Map<(PartitionID, u128, ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
-            IndexType::Binary => {
-                let map: Map<(PartitionID, &[u8], ContractID), u8> = Map::new(map_name);
-                map.clear(deps.storage);
-            },
+    let (progress, is_complete) =
+        delete_index_page(deps.storage, &index_name, params.limit, env.block.time)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", action),
+            attr("index", index_name),
+            attr("removed", progress.removed.to_string()),
+            attr("is_complete", is_complete.to_string()),
+        ])
+        .set_data(to_json_binary(&progress)?))
+}
+
+/// Remove the entries and stored values of the next page of contract IDs from
+/// a custom index, returning the progress so far and whether it's complete.
+fn delete_index_page(
+    storage: &mut dyn Storage,
+    index_name: &String,
+    limit: Option<u32>,
+    time: Timestamp,
+) -> Result<(IndexDeletionProgress, bool), ContractError> {
+    // Compound indices must be deleted before their components
+    if let Some(result) = INDEX_COMPOUNDS
+        .prefix(index_name)
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+    {
+        return Err(ContractError::ValidationError {
+            reason: format!(
+                "index '{}' is a component of compound index '{}'",
+                index_name, result?
+            ),
+        });
+    }
+
+    let mut meta = INDEX_METADATA
+        .may_load(storage, index_name.clone())?
+        .ok_or_else(|| ContractError::UnknownIndex {
+            index: index_name.clone(),
+        })?;
+
+    let mut progress = match meta.deletion.take() {
+        Some(progress) => progress,
+        None => {
+            REQUIRED_INDICES.remove(storage, index_name);
+            REINDEX_PROGRESS.remove(storage, index_name);
+            IndexDeletionProgress {
+                cursor: None,
+                removed: Uint64::zero(),
+                started_at: time,
+            }
+        },
+    };
+
+    let limit = limit.unwrap_or(100).clamp(1, 500) as usize;
+
+    // Contract IDs are assigned in sequence, so scan the next page of IDs for a
+    // stored value of the index, whether the contract is live or archived.
+    let start: ContractID = progress.cursor.map_or(0, |id| id.u64()) + 1;
+    let max_id: ContractID = CONTRACT_ID_COUNTER.load(storage)?.u64();
+    let stop: ContractID = max_id.min(start + limit as u64 - 1);
+
+    for contract_id in start..=stop {
+        let value_type = match CONTRACT_INDEX_TYPES.may_load(storage, (contract_id, index_name))? {
+            Some(value_type) => value_type,
+            None => continue,
         };
+        // Values of another type than the index were never entered in it, and
+        // archived contracts have no metadata and no entries in the index.
+        if value_type == meta.index_type {
+            if let Some(contract_meta) = CONTRACT_METADATA.may_load(storage, contract_id)? {
+                set_custom_index_entry(
+                    storage,
+                    contract_meta.partition,
+                    contract_id,
+                    index_name,
+                    &value_type,
+                    false,
+                )?;
+            }
+        }
+        remove_index_value(storage, contract_id, index_name, &value_type)?;
+        progress.removed += Uint64::one();
+    }
+
+    if start <= stop {
+        progress.cursor = Some(stop.into());
+    }
+
+    let is_complete = stop >= max_id;
+    if is_complete {
+        INDEX_METADATA.remove(storage, index_name.clone());
     } else {
-        return Err(ContractError::NotAuthorized {
-            reason: format!("index metadata does not exist for '{}'", index_name),
-        });
+        meta.deletion = Some(progress.clone());
+        INDEX_METADATA.save(storage, index_name.clone(), &meta)?;
+    }
+
+    Ok((progress, is_complete))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        execute::client::update::{set_custom_index_entries, update_indices},
+        models::ContractMetadata,
+        msg::{IndexMetadata, IndexType, KeyValue},
+        state::{CustomIndexMap, PartitionID},
+        util::build_index_storage_key,
+    };
+    use cosmwasm_std::{testing::MockApi, Addr, Binary, MemoryStorage};
+    use cw_storage_plus::Map;
+
+    fn save_contract(
+        storage: &mut dyn Storage,
+        partition: PartitionID,
+        contract_id: ContractID,
+    ) {
+        let meta = ContractMetadata {
+            id: contract_id.into(),
+            code_id: Uint64::one(),
+            created_by: Addr::unchecked("creator"),
+            created_at: Timestamp::from_seconds(0),
+            created_at_height: Uint64::zero(),
+            is_managed: true,
+            admin: None,
+            partition,
+        };
+        CONTRACT_METADATA.save(storage, contract_id, &meta).unwrap();
+    }
+
+    fn set_value(
+        storage: &mut dyn Storage,
+        partition: PartitionID,
+        contract_id: ContractID,
+        index_name: &String,
+        value: &str,
+    ) {
+        let kv = KeyValue::Binary(index_name.clone(), Some(Binary::from(value.as_bytes())));
+        update_indices(
+            &MockApi::default(),
+            storage,
+            partition,
+            contract_id,
+            vec![kv],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn batched_delete_resumes_from_saved_progress() {
+        let mut storage = MemoryStorage::new();
+        let index_name = "code".to_owned();
+        let meta = IndexMetadata {
+            index_type: IndexType::Binary,
+            name: index_name.clone(),
+            size: Uint64::zero(),
+            constraints: None,
+            unique: None,
+            strings: None,
+            multi: None,
+            facets: None,
+            text: None,
+            deletion: None,
+        };
+        INDEX_METADATA
+            .save(&mut storage, index_name.clone(), &meta)
+            .unwrap();
+        CONTRACT_ID_COUNTER
+            .save(&mut storage, &Uint64::new(5))
+            .unwrap();
+
+        // Contracts 1 to 3 are live, 4 is archived and 5 has no value
+        for contract_id in 1..=5 {
+            save_contract(&mut storage, 1, contract_id);
+            if contract_id < 5 {
+                set_value(&mut storage, 1, contract_id, &index_name, "a");
+            }
+        }
+        set_custom_index_entries(&mut storage, 1, 4, false).unwrap();
+        CONTRACT_METADATA.remove(&mut storage, 4);
+
+        let index_storage_key = build_index_storage_key(&index_name);
+        let index: CustomIndexMap<&[u8]> = Map::new(&index_storage_key);
+        let count_entries =
+            |storage: &dyn Storage| index.keys(storage, None, None, Order::Ascending).count();
+        assert_eq!(count_entries(&storage), 3);
+
+        let (progress, is_complete) = delete_index_page(
+            &mut storage,
+            &index_name,
+            Some(2),
+            Timestamp::from_seconds(1),
+        )
+        .unwrap();
+        assert!(!is_complete);
+        assert_eq!(progress.cursor, Some(Uint64::new(2)));
+        assert_eq!(progress.removed, Uint64::new(2));
+        assert_eq!(count_entries(&storage), 1);
+        assert!(!CONTRACT_INDEX_TYPES.has(&storage, (2, &index_name)));
+        assert!(CONTRACT_INDEX_TYPES.has(&storage, (3, &index_name)));
+
+        // Writes are refused while the deletion is in progress
+        let kv = KeyValue::Binary(index_name.clone(), Some(Binary::from(b"b".as_slice())));
+        let err = update_indices(&MockApi::default(), &mut storage, 1, 3, vec![kv]).unwrap_err();
+        assert!(matches!(err, ContractError::IndexDeleting { .. }));
+
+        let (progress, is_complete) = delete_index_page(
+            &mut storage,
+            &index_name,
+            Some(2),
+            Timestamp::from_seconds(2),
+        )
+        .unwrap();
+        assert!(!is_complete);
+        assert_eq!(progress.cursor, Some(Uint64::new(4)));
+        assert_eq!(progress.removed, Uint64::new(4));
+        assert_eq!(progress.started_at, Timestamp::from_seconds(1));
+        assert_eq!(count_entries(&storage), 0);
+        assert!(!CONTRACT_INDEX_TYPES.has(&storage, (4, &index_name)));
+
+        let (progress, is_complete) = delete_index_page(
+            &mut storage,
+            &index_name,
+            Some(2),
+            Timestamp::from_seconds(3),
+        )
+        .unwrap();
+        assert!(is_complete);
+        assert_eq!(progress.cursor, Some(Uint64::new(5)));
+        assert_eq!(progress.removed, Uint64::new(4));
+        assert!(!INDEX_METADATA.has(&storage, index_name));
     }
-    Ok(Response::new().add_attribute("action", "delete_index"))
 }
@@ -0,0 +1,80 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{attr, to_json_binary, Order, Response, Uint64};
+use cw_storage_plus::Bound;
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    msg::{ManagedIndexingParams, ManagedIndexingProgress},
+    state::{
+        ensure_allowed_by_acl, ContractID, CONTRACT_METADATA, IX_MANAGED,
+        MANAGED_INDEXING_PROGRESS, X,
+    },
+};
+
+/// Add existing contracts to the managed status index, resuming from where
+/// the last call left off. Contracts created, refreshed or updated since the
+/// index was introduced are already in it, so saving their entries again is
+/// harmless. Archived contracts are skipped, as they have no metadata.
+pub fn on_execute(
+    ctx: Context,
+    params: ManagedIndexingParams,
+) -> Result<Response, ContractError> {
+    let action = "index_managed_contracts";
+    let Context { deps, info, env } = ctx;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/index-managed-contracts")?;
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 500) as usize;
+
+    // Resume saved progress unless it's complete or a restart is requested
+    let mut progress = match MANAGED_INDEXING_PROGRESS.may_load(deps.storage)? {
+        Some(progress) if !progress.is_complete && !params.restart.unwrap_or(false) => progress,
+        _ => ManagedIndexingProgress {
+            cursor: None,
+            scanned: Uint64::zero(),
+            is_complete: false,
+            started_at: env.block.time,
+            updated_at: env.block.time,
+        },
+    };
+
+    let contract_ids: Vec<ContractID> = CONTRACT_METADATA
+        .keys(
+            deps.storage,
+            progress
+                .cursor
+                .map(|id| Bound::Exclusive((id.u64(), PhantomData))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for contract_id in contract_ids.iter() {
+        let meta = CONTRACT_METADATA.load(deps.storage, *contract_id)?;
+        IX_MANAGED.save(
+            deps.storage,
+            (meta.partition, meta.is_managed.into(), *contract_id),
+            &X,
+        )?;
+    }
+
+    progress.scanned += Uint64::from(contract_ids.len() as u64);
+    progress.updated_at = env.block.time;
+    progress.is_complete = contract_ids.len() < limit;
+    if let Some(contract_id) = contract_ids.last() {
+        progress.cursor = Some((*contract_id).into());
+    }
+
+    MANAGED_INDEXING_PROGRESS.save(deps.storage, &progress)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", action),
+            attr("count", contract_ids.len().to_string()),
+            attr("is_complete", progress.is_complete.to_string()),
+        ])
+        .set_data(to_json_binary(&progress)?))
+}
@@ -0,0 +1,98 @@
+use cosmwasm_std::{attr, to_json_binary, Event, Response, WasmMsg};
+
+use crate::{
+    context::Context,
+    ensure::ensure_authorized_code_id,
+    error::ContractError,
+    msg::{ContractsMigrationParams, ContractsMigrationResponse},
+    state::{
+        ensure_allowed_by_acl, load_contract_addr, select_contract_ids, CONTRACT_METADATA,
+        IX_CODE_ID, X,
+    },
+};
+
+/// Migrate a page of managed contracts matching the selector to a new code ID.
+/// Contracts that aren't managed by the table or that already use the code ID
+/// are skipped. Returns a cursor for the next page, if any.
+pub fn on_execute(
+    ctx: Context,
+    params: ContractsMigrationParams,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, .. } = ctx;
+    let action = "migrate_contracts";
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/migrate-contracts")?;
+    ensure_authorized_code_id(deps.storage, params.code_id.into())?;
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let contract_ids = select_contract_ids(
+        deps.storage,
+        &params.select,
+        params.cursor.map(|c| c.u64()),
+        limit,
+    )?;
+
+    let mut resp = Response::new();
+    let mut migrated: u32 = 0;
+    let mut skipped: u32 = 0;
+
+    for contract_id in contract_ids.iter() {
+        let mut meta = CONTRACT_METADATA.load(deps.storage, *contract_id)?;
+
+        // The table can only migrate contracts for which it's the wasm admin
+        if !meta.is_managed || meta.code_id == params.code_id {
+            skipped += 1;
+            continue;
+        }
+
+        let contract_addr = load_contract_addr(deps.storage, *contract_id)?;
+        let prev_code_id = meta.code_id;
+
+        IX_CODE_ID.remove(
+            deps.storage,
+            (meta.partition, prev_code_id.into(), *contract_id),
+        );
+        IX_CODE_ID.save(
+            deps.storage,
+            (meta.partition, params.code_id.into(), *contract_id),
+            &X,
+        )?;
+
+        meta.code_id = params.code_id;
+        CONTRACT_METADATA.save(deps.storage, *contract_id, &meta)?;
+
+        resp = resp
+            .add_message(WasmMsg::Migrate {
+                contract_addr: contract_addr.to_string(),
+                new_code_id: params.code_id.into(),
+                msg: params.msg.clone(),
+            })
+            .add_event(
+                Event::new("migrate")
+                    .add_attribute("contract_address", contract_addr.to_string())
+                    .add_attribute("contract_id", contract_id.to_string())
+                    .add_attribute("prev_code_id", prev_code_id.to_string())
+                    .add_attribute("code_id", params.code_id.to_string()),
+            );
+
+        migrated += 1;
+    }
+
+    let cursor = if contract_ids.len() == limit {
+        contract_ids.last().map(|id| (*id).into())
+    } else {
+        None
+    };
+
+    Ok(resp
+        .add_attributes(vec![
+            attr("action", action),
+            attr("migrated", migrated.to_string()),
+            attr("skipped", skipped.to_string()),
+        ])
+        .set_data(to_json_binary(&ContractsMigrationResponse {
+            migrated,
+            skipped,
+            cursor,
+        })?))
+}
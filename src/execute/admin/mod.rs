@@ -1,11 +1,19 @@
 pub mod assign_groups;
+pub mod clear_reply_jobs;
+pub mod create_compound_index;
 pub mod create_group;
 pub mod create_index;
 pub mod create_partition;
+pub mod delete_compound_index;
 pub mod delete_index;
+pub mod index_managed_contracts;
+pub mod migrate_contracts;
+pub mod reindex;
+pub mod restore;
 pub mod revert_config;
 pub mod set_owner;
 pub mod set_partition;
 pub mod unsuspend;
 pub mod update_config;
+pub mod update_contract_admin;
 pub mod update_info;
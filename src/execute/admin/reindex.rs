@@ -0,0 +1,199 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{attr, to_json_binary, Order, Response, Storage, Uint64};
+use cw_storage_plus::Bound;
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    execute::client::update::{set_custom_index_entry, update_compound_index},
+    msg::{ReindexParams, ReindexProgress},
+    state::{
+        ensure_allowed_by_acl, load_index_set_values, ContractID, COMPOUND_INDEX_METADATA,
+        CONTRACT_INDEX_TYPES, CONTRACT_METADATA, INDEX_METADATA, REINDEX_PROGRESS, VALUES_COMPOUND,
+    },
+};
+
+/// Insert missing entries into a custom or compound index from the values
+/// already stored for each contract, resuming from where the last call left
+/// off. Aggregates, facet counts and text entries are backfilled for values
+/// that were indexed before they were maintained, and the index size is
+/// recounted from zero over a full run. Archived contracts are skipped, as
+/// they have no metadata.
+///
+/// Live contracts are scanned rather than CONTRACT_INDEX_TYPES, as the latter
+/// is keyed by contract ID first. Paging through it for one index would mean
+/// reading every stored value of every other index, whereas a direct lookup
+/// per contract bounds each call by its page size and works the same way for
+/// compound indices, which have no stored types of their own.
+pub fn on_execute(
+    ctx: Context,
+    params: ReindexParams,
+) -> Result<Response, ContractError> {
+    let action = "reindex";
+    let Context { deps, info, env } = ctx;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/reindex")?;
+
+    let index_name = params.index;
+    let maybe_meta = INDEX_METADATA.may_load(deps.storage, index_name.clone())?;
+    if maybe_meta
+        .as_ref()
+        .map_or(false, |meta| meta.deletion.is_some())
+    {
+        return Err(ContractError::IndexDeleting { index: index_name });
+    }
+    let index_type = maybe_meta.as_ref().map(|meta| meta.index_type.clone());
+    let is_multi = maybe_meta.map_or(false, |meta| meta.multi.unwrap_or(false));
+
+    if index_type.is_none() {
+        match COMPOUND_INDEX_METADATA.may_load(deps.storage, index_name.clone())? {
+            None => return Err(ContractError::UnknownIndex { index: index_name }),
+            Some(meta) if meta.deletion.is_some() => {
+                return Err(ContractError::IndexDeleting { index: index_name })
+            },
+            _ => {},
+        }
+    }
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 500) as usize;
+
+    // Resume saved progress unless it's complete or a restart is requested,
+    // in which case the index size is counted again from zero.
+    let mut progress = match REINDEX_PROGRESS.may_load(deps.storage, &index_name)? {
+        Some(progress) if !progress.is_complete && !params.restart.unwrap_or(false) => progress,
+        _ => {
+            save_index_size(deps.storage, &index_name, Uint64::zero())?;
+            ReindexProgress {
+                index: index_name.clone(),
+                cursor: None,
+                scanned: Uint64::zero(),
+                inserted: Uint64::zero(),
+                skipped: Uint64::zero(),
+                is_complete: false,
+                started_at: env.block.time,
+                updated_at: env.block.time,
+            }
+        },
+    };
+
+    // Entries inserted below add to the stored size as they go, so the size
+    // for this page is set from its starting value plus every entry found.
+    let start_size = load_index_size(deps.storage, &index_name)?;
+    let mut n_entries = Uint64::zero();
+
+    let contract_ids: Vec<ContractID> = CONTRACT_METADATA
+        .keys(
+            deps.storage,
+            progress
+                .cursor
+                .map(|id| Bound::Exclusive((id.u64(), PhantomData))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for contract_id in contract_ids.iter() {
+        let partition = CONTRACT_METADATA
+            .load(deps.storage, *contract_id)?
+            .partition;
+
+        let is_inserted = if let Some(index_type) = &index_type {
+            // Skip contracts without a value of the index's declared type
+            match CONTRACT_INDEX_TYPES.may_load(deps.storage, (*contract_id, &index_name))? {
+                Some(value_type) if value_type == *index_type => {
+                    match set_custom_index_entry(
+                        deps.storage,
+                        partition,
+                        *contract_id,
+                        &index_name,
+                        index_type,
+                        true,
+                    ) {
+                        Ok(is_changed) => {
+                            n_entries += if is_multi {
+                                let values =
+                                    load_index_set_values(deps.storage, *contract_id, &index_name)?;
+                                Uint64::from(values.len() as u64)
+                            } else {
+                                Uint64::one()
+                            };
+                            is_changed
+                        },
+                        Err(ContractError::UniqueIndexConflict { .. }) => {
+                            progress.skipped += Uint64::one();
+                            false
+                        },
+                        Err(e) => return Err(e),
+                    }
+                },
+                _ => false,
+            }
+        } else {
+            let is_changed =
+                update_compound_index(deps.storage, partition, *contract_id, &index_name)?;
+            if VALUES_COMPOUND.has(deps.storage, (*contract_id, &index_name)) {
+                n_entries += Uint64::one();
+            }
+            is_changed
+        };
+
+        if is_inserted {
+            progress.inserted += Uint64::one();
+        }
+    }
+
+    save_index_size(deps.storage, &index_name, start_size + n_entries)?;
+
+    progress.scanned += Uint64::from(contract_ids.len() as u64);
+    progress.updated_at = env.block.time;
+    progress.is_complete = contract_ids.len() < limit;
+    if let Some(contract_id) = contract_ids.last() {
+        progress.cursor = Some((*contract_id).into());
+    }
+
+    REINDEX_PROGRESS.save(deps.storage, &index_name, &progress)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", action),
+            attr("index", index_name),
+            attr("count", contract_ids.len().to_string()),
+        ])
+        .set_data(to_json_binary(&progress)?))
+}
+
+/// Load the size of a custom or compound index.
+fn load_index_size(
+    storage: &dyn Storage,
+    index_name: &String,
+) -> Result<Uint64, ContractError> {
+    Ok(
+        match INDEX_METADATA.may_load(storage, index_name.clone())? {
+            Some(meta) => meta.size,
+            None => {
+                COMPOUND_INDEX_METADATA
+                    .load(storage, index_name.clone())?
+                    .size
+            },
+        },
+    )
+}
+
+/// Overwrite the size of a custom or compound index.
+fn save_index_size(
+    storage: &mut dyn Storage,
+    index_name: &String,
+    size: Uint64,
+) -> Result<(), ContractError> {
+    if let Some(mut meta) = INDEX_METADATA.may_load(storage, index_name.clone())? {
+        meta.size = size;
+        INDEX_METADATA.save(storage, index_name.clone(), &meta)?;
+    } else {
+        let mut meta = COMPOUND_INDEX_METADATA.load(storage, index_name.clone())?;
+        meta.size = size;
+        COMPOUND_INDEX_METADATA.save(storage, index_name.clone(), &meta)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use cosmwasm_std::{to_json_binary, Addr, Event, Order, Response, Uint64, WasmMsg};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    execute::client::{
+        create::set_builtin_index_entries,
+        update::{set_custom_index_entries, set_group_aggregate_entries},
+    },
+    lifecycle::{LifecycleArgs, LifecycleExecuteMsg, LifecycleExecuteMsgEnvelope},
+    state::{
+        append_group, ensure_allowed_by_acl, ensure_partition_exists, exists_contract_address,
+        increment_tag_count, set_contract_expiry, ARCHIVED_ADDR_2_ID, CONTRACT_ADDR_2_ID,
+        CONTRACT_DYN_METADATA, CONTRACT_ID_2_ADDR, CONTRACT_METADATA, CONTRACT_TAGS,
+        CONTRACT_TOMBSTONES, CONTRACT_USES_LIFECYCLE_HOOKS, GROUP_METADATA, IX_TAG,
+        PARTITION_SIZES, REL_ADDR_2_ID, REL_ID_2_ADDR, UNIQUE, X,
+    },
+    util::trim_padding,
+};
+
+/// Re-insert an archived contract into the table, in its original partition.
+pub fn on_execute(
+    ctx: Context,
+    contract_addr: Addr,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, env } = ctx;
+    let action = "restore";
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/restore")?;
+
+    let contract_id: u64 = ARCHIVED_ADDR_2_ID
+        .may_load(deps.storage, &contract_addr)?
+        .ok_or_else(|| ContractError::NotAuthorized {
+            reason: format!("contract {} is not archived", contract_addr),
+        })?
+        .into();
+
+    // The address may have been registered again since it was archived
+    if exists_contract_address(deps.storage, &contract_addr) {
+        return Err(ContractError::ValidationError {
+            reason: format!("contract {} already exists", contract_addr),
+        });
+    }
+
+    let tombstone = CONTRACT_TOMBSTONES.load(deps.storage, contract_id)?;
+    let p = tombstone.meta.partition;
+
+    ensure_partition_exists(deps.storage, p)?;
+
+    // Restore metadata and mappings between contract ID <-> contract Addr
+    CONTRACT_METADATA.save(deps.storage, contract_id, &tombstone.meta)?;
+    if let Some(dyn_meta) = &tombstone.dyn_meta {
+        CONTRACT_DYN_METADATA.save(deps.storage, contract_id, dyn_meta)?;
+    }
+    CONTRACT_ID_2_ADDR.save(deps.storage, contract_id, &contract_addr)?;
+    CONTRACT_ADDR_2_ID.save(deps.storage, &contract_addr, &contract_id.into())?;
+
+    PARTITION_SIZES.update(deps.storage, p, |maybe_n| -> Result<_, ContractError> {
+        maybe_n
+            .unwrap_or_default()
+            .checked_add(Uint64::one())
+            .map_err(|e| ContractError::UnexpectedError {
+                reason: e.to_string(),
+            })
+    })?;
+
+    // Re-insert into built-in and custom indices
+    set_builtin_index_entries(deps.storage, p, contract_id, true)?;
+    set_custom_index_entries(deps.storage, p, contract_id, true)?;
+
+    // Restore tags, unless a unique tag has been taken in the meantime
+    for (tag, uniqueness) in tombstone.tags.iter() {
+        for entry in IX_TAG
+            .prefix((p, tag))
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(1)
+        {
+            let (_, other_uniqueness) = entry?;
+            if other_uniqueness == UNIQUE || *uniqueness == UNIQUE {
+                return Err(ContractError::ValidationError {
+                    reason: format!("tag {} is unique and already used", trim_padding(tag)),
+                });
+            }
+        }
+        IX_TAG.save(deps.storage, (p, tag, contract_id), uniqueness)?;
+        CONTRACT_TAGS.save(deps.storage, (contract_id, tag.clone()), &X)?;
+        increment_tag_count(deps.storage, p, tag)?;
+    }
+
+    // Restore relationships, unless a unique one has been taken in the meantime
+    for (name, addr_str, uniqueness) in tombstone.relationships.iter() {
+        for entry in REL_ADDR_2_ID
+            .prefix((addr_str.clone(), name.clone()))
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(1)
+        {
+            let (_, other_uniqueness) = entry?;
+            if other_uniqueness == UNIQUE || *uniqueness == UNIQUE {
+                return Err(ContractError::ValidationError {
+                    reason: format!("Relationship {} is unique", trim_padding(name)),
+                });
+            }
+        }
+        REL_ADDR_2_ID.save(
+            deps.storage,
+            (addr_str.clone(), name.clone(), contract_id.to_string()),
+            uniqueness,
+        )?;
+        REL_ID_2_ADDR.save(
+            deps.storage,
+            (contract_id, name.clone(), addr_str.clone()),
+            uniqueness,
+        )?;
+    }
+
+    // Drop an expiry that has already passed, or the next Expire would only
+    // archive the contract again.
+    let expiry = tombstone
+        .expiry
+        .clone()
+        .filter(|expiry| !expiry.expires.is_expired(&env.block));
+    set_contract_expiry(deps.storage, contract_id, expiry)?;
+
+    // Restore membership in groups that still exist
+    for group_id in tombstone.groups.iter() {
+        if GROUP_METADATA.has(deps.storage, *group_id) {
+            append_group(deps.storage, *group_id, contract_id)?;
+            set_group_aggregate_entries(deps.storage, *group_id, contract_id, true)?;
+        }
+    }
+
+    CONTRACT_TOMBSTONES.remove(deps.storage, contract_id);
+    ARCHIVED_ADDR_2_ID.remove(deps.storage, &contract_addr);
+
+    let mut resp = Response::new().add_attribute("action", action).add_event(
+        Event::new("restore")
+            .add_attribute("contract_address", contract_addr.to_string())
+            .add_attribute("contract_id", contract_id.to_string()),
+    );
+
+    if CONTRACT_USES_LIFECYCLE_HOOKS
+        .may_load(deps.storage, contract_id)?
+        .unwrap_or_default()
+    {
+        resp = resp.add_message(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_json_binary(&LifecycleExecuteMsgEnvelope::Lifecycle(
+                LifecycleExecuteMsg::Resume(LifecycleArgs {
+                    table: env.contract.address.clone(),
+                    initiator: info.sender.clone(),
+                }),
+            ))?,
+            funds: vec![],
+        });
+    }
+
+    Ok(resp)
+}
@@ -1,20 +1,15 @@
 use cosmwasm_std::{Addr, Order, Response, StdResult, Storage, Uint64};
-use cw_storage_plus::Map;
 
 use crate::{
     context::Context,
     error::ContractError,
-    msg::{IndexType, PartitionSelector},
+    execute::client::{create::set_builtin_index_entries, update::set_custom_index_entries},
+    msg::PartitionSelector,
     state::{
         decrement_tag_count, ensure_allowed_by_acl, ensure_contract_not_suspended,
         ensure_partition_exists, increment_tag_count, load_contract_id, resolve_partition_id,
-        ContractID, CustomIndexMap, PartitionID, CONTRACT_DYN_METADATA, CONTRACT_INDEX_TYPES,
-        CONTRACT_METADATA, CONTRACT_TAGS, IX_CODE_ID, IX_CONTRACT_ID, IX_CREATED_AT, IX_CREATED_BY,
-        IX_REV, IX_TAG, IX_UPDATED_AT, IX_UPDATED_BY, PARTITION_SIZES, VALUES_BINARY, VALUES_BOOL,
-        VALUES_I32, VALUES_STRING, VALUES_TIME, VALUES_U128, VALUES_U16, VALUES_U32, VALUES_U64,
-        VALUES_U8, X,
+        ContractID, PartitionID, CONTRACT_METADATA, CONTRACT_TAGS, IX_TAG, PARTITION_SIZES,
     },
-    util::build_index_storage_key,
 };
 
 /// Move the contract to a new partition.
@@ -73,9 +68,11 @@ pub fn move_tags(
         decrement_tag_count(storage, src, &tag)?;
         increment_tag_count(storage, dst, &tag)?;
 
-        // Move the tag to a new partition in the tags index
+        // Move the tag to a new partition in the tags index, keeping whether
+        // or not it's unique
+        let uniqueness = IX_TAG.load(storage, (src, &tag, contract_id))?;
         IX_TAG.remove(storage, (src, &tag, contract_id));
-        IX_TAG.save(storage, (dst, &tag, contract_id), &X)?;
+        IX_TAG.save(storage, (dst, &tag, contract_id), &uniqueness)?;
     }
 
     Ok(())
@@ -91,6 +88,18 @@ pub fn update_contract_partition(
     move_custom_indices(storage, contract_id, src, dst)?;
     move_tags(storage, contract_id, src, dst)?;
 
+    CONTRACT_METADATA.update(
+        storage,
+        contract_id,
+        |maybe_meta| -> Result<_, ContractError> {
+            let mut meta = maybe_meta.ok_or_else(|| ContractError::UnexpectedError {
+                reason: format!("contract {} metadata not found", contract_id),
+            })?;
+            meta.partition = dst;
+            Ok(meta)
+        },
+    )?;
+
     PARTITION_SIZES.update(storage, src, |maybe_n| -> Result<_, ContractError> {
         maybe_n
             .unwrap_or_default()
@@ -100,7 +109,7 @@ pub fn update_contract_partition(
             })
     })?;
 
-    PARTITION_SIZES.update(storage, src, |maybe_n| -> Result<_, ContractError> {
+    PARTITION_SIZES.update(storage, dst, |maybe_n| -> Result<_, ContractError> {
         maybe_n
             .unwrap_or_default()
             .checked_add(Uint64::one())
@@ -118,44 +127,8 @@ fn move_standard_indices(
     src: PartitionID,
     dst: PartitionID,
 ) -> Result<(), ContractError> {
-    // Update core metadata indices
-    let meta = CONTRACT_METADATA.load(storage, contract_id)?;
-
-    IX_CONTRACT_ID.remove(storage, (src, contract_id, contract_id));
-    IX_CONTRACT_ID.save(storage, (dst, contract_id.into(), contract_id), &X)?;
-
-    IX_CODE_ID.remove(storage, (src, meta.code_id.into(), contract_id));
-    IX_CODE_ID.save(storage, (dst, meta.code_id.into(), contract_id), &X)?;
-
-    IX_CREATED_BY.remove(storage, (src, meta.created_by.to_string(), contract_id));
-    IX_CREATED_BY.save(storage, (dst, meta.created_by.to_string(), contract_id), &X)?;
-
-    IX_CREATED_AT.remove(storage, (src, meta.created_at.nanos(), contract_id));
-    IX_CREATED_AT.save(storage, (dst, meta.created_at.nanos(), contract_id), &X)?;
-
-    // Update dynamic metadata indices
-    if let Some(up_meta) = CONTRACT_DYN_METADATA.may_load(storage, contract_id)? {
-        IX_UPDATED_BY.remove(storage, (src, up_meta.updated_by.to_string(), contract_id));
-        IX_UPDATED_BY.save(
-            storage,
-            (dst, up_meta.updated_by.to_string(), contract_id),
-            &X,
-        )?;
-        IX_UPDATED_AT.remove(storage, (src, up_meta.updated_at.nanos(), contract_id));
-        IX_UPDATED_AT.save(storage, (dst, up_meta.updated_at.nanos(), contract_id), &X)?;
-        IX_REV.remove(storage, (src, up_meta.rev.into(), contract_id));
-        IX_REV.save(storage, (dst, up_meta.rev.into(), contract_id), &X)?;
-    } else {
-        // Dynamic metadata doesn't exist until first update. in this case,
-        // assume existing values are initial values.
-        IX_UPDATED_BY.remove(storage, (src, meta.created_at.to_string(), contract_id));
-        IX_UPDATED_BY.save(storage, (dst, meta.created_at.to_string(), contract_id), &X)?;
-        IX_UPDATED_AT.remove(storage, (src, meta.created_at.nanos(), contract_id));
-        IX_UPDATED_AT.save(storage, (dst, meta.created_at.nanos(), contract_id), &X)?;
-        IX_REV.remove(storage, (src, 1, contract_id));
-        IX_REV.save(storage, (dst, 1, contract_id), &X)?;
-    }
-
+    set_builtin_index_entries(storage, src, contract_id, false)?;
+    set_builtin_index_entries(storage, dst, contract_id, true)?;
     Ok(())
 }
 
@@ -165,77 +138,7 @@ pub fn move_custom_indices(
     src: PartitionID,
     dst: PartitionID,
 ) -> Result<(), ContractError> {
-    let entries: Vec<(String, IndexType)> = CONTRACT_INDEX_TYPES
-        .prefix(contract_id)
-        .range(storage, None, None, Order::Ascending)
-        .filter_map(|r| r.ok())
-        .collect();
-
-    for (index_name, index_type) in entries.iter() {
-        let index_storage_key = build_index_storage_key(index_name);
-        match index_type {
-            IndexType::String => {
-                let index: CustomIndexMap<&String> = Map::new(&index_storage_key);
-                let value = VALUES_STRING.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, &value, contract_id));
-                index.save(storage, (dst, &value, contract_id), &X)?;
-            },
-            IndexType::Bool => {
-                let index: CustomIndexMap<u8> = Map::new(&index_storage_key);
-                let value = VALUES_BOOL.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Timestamp => {
-                let index: CustomIndexMap<u64> = Map::new(&index_storage_key);
-                let value = VALUES_TIME.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.nanos(), contract_id));
-                index.save(storage, (dst, value.nanos(), contract_id), &X)?;
-            },
-            IndexType::Int32 => {
-                let index: CustomIndexMap<i32> = Map::new(&index_storage_key);
-                let value = VALUES_I32.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Uint8 => {
-                let index: CustomIndexMap<u8> = Map::new(&index_storage_key);
-                let value = VALUES_U8.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Uint16 => {
-                let index: CustomIndexMap<u16> = Map::new(&index_storage_key);
-                let value = VALUES_U16.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Uint32 => {
-                let index: CustomIndexMap<u32> = Map::new(&index_storage_key);
-                let value = VALUES_U32.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Uint64 => {
-                let index: CustomIndexMap<u64> = Map::new(&index_storage_key);
-                let value = VALUES_U64.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Uint128 => {
-                let index: CustomIndexMap<u128> = Map::new(&index_storage_key);
-                let value = VALUES_U128.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.into(), contract_id));
-                index.save(storage, (dst, value.into(), contract_id), &X)?;
-            },
-            IndexType::Binary => {
-                let index: CustomIndexMap<&[u8]> = Map::new(&index_storage_key);
-                let value = VALUES_BINARY.load(storage, (contract_id, &index_storage_key))?;
-                index.remove(storage, (src, value.as_slice(), contract_id));
-                index.save(storage, (dst, value.as_slice(), contract_id), &X)?;
-            },
-        }
-    }
-
+    set_custom_index_entries(storage, src, contract_id, false)?;
+    set_custom_index_entries(storage, dst, contract_id, true)?;
     Ok(())
 }
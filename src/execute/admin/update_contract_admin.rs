@@ -0,0 +1,139 @@
+use cosmwasm_std::{attr, to_json_binary, Addr, DepsMut, Env, Event, Response, WasmMsg};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    msg::{
+        ContractAdminClearParams, ContractAdminUpdateParams, ContractAdminUpdateResponse,
+        ContractSelector,
+    },
+    state::{
+        ensure_allowed_by_acl, load_contract_addr, select_contract_ids, CONTRACT_METADATA,
+        IX_MANAGED, X,
+    },
+};
+
+/// Hand over the wasm admin of a page of managed contracts to a new address.
+pub fn on_update(
+    ctx: Context,
+    params: ContractAdminUpdateParams,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, env } = ctx;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/update-contract-admin")?;
+
+    let admin = deps.api.addr_validate(params.admin.as_str())?;
+
+    update_admins(
+        deps,
+        env,
+        "update_contract_admin",
+        &params.select,
+        Some(admin),
+        params.cursor.map(|c| c.u64()),
+        params.limit,
+    )
+}
+
+/// Clear the wasm admin of a page of managed contracts, making them immutable.
+pub fn on_clear(
+    ctx: Context,
+    params: ContractAdminClearParams,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, env } = ctx;
+
+    ensure_allowed_by_acl(&deps, &info.sender, "/table/clear-contract-admin")?;
+
+    update_admins(
+        deps,
+        env,
+        "clear_contract_admin",
+        &params.select,
+        None,
+        params.cursor.map(|c| c.u64()),
+        params.limit,
+    )
+}
+
+fn update_admins(
+    deps: DepsMut,
+    env: Env,
+    action: &str,
+    selector: &ContractSelector,
+    maybe_admin: Option<Addr>,
+    cursor: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(20).clamp(1, 100) as usize;
+    let contract_ids = select_contract_ids(deps.storage, selector, cursor, limit)?;
+
+    // The table remains the admin if it's given as the new admin
+    let is_managed = maybe_admin == Some(env.contract.address.clone());
+
+    let mut resp = Response::new();
+    let mut updated: u32 = 0;
+    let mut skipped: u32 = 0;
+
+    for contract_id in contract_ids.iter() {
+        let mut meta = CONTRACT_METADATA.load(deps.storage, *contract_id)?;
+
+        // The table can only change the admin of contracts it's the admin of
+        if !meta.is_managed || is_managed {
+            skipped += 1;
+            continue;
+        }
+
+        let contract_addr = load_contract_addr(deps.storage, *contract_id)?;
+
+        IX_MANAGED.remove(
+            deps.storage,
+            (meta.partition, meta.is_managed.into(), *contract_id),
+        );
+        IX_MANAGED.save(
+            deps.storage,
+            (meta.partition, is_managed.into(), *contract_id),
+            &X,
+        )?;
+
+        meta.is_managed = is_managed;
+        meta.admin = maybe_admin.clone();
+        CONTRACT_METADATA.save(deps.storage, *contract_id, &meta)?;
+
+        let mut event = Event::new(action)
+            .add_attribute("contract_address", contract_addr.to_string())
+            .add_attribute("contract_id", contract_id.to_string());
+
+        resp = resp.add_message(if let Some(admin) = &maybe_admin {
+            event = event.add_attribute("admin", admin.to_string());
+            WasmMsg::UpdateAdmin {
+                contract_addr: contract_addr.to_string(),
+                admin: admin.to_string(),
+            }
+        } else {
+            WasmMsg::ClearAdmin {
+                contract_addr: contract_addr.to_string(),
+            }
+        });
+
+        resp = resp.add_event(event);
+        updated += 1;
+    }
+
+    let cursor = if contract_ids.len() == limit {
+        contract_ids.last().map(|id| (*id).into())
+    } else {
+        None
+    };
+
+    Ok(resp
+        .add_attributes(vec![
+            attr("action", action),
+            attr("updated", updated.to_string()),
+            attr("skipped", skipped.to_string()),
+        ])
+        .set_data(to_json_binary(&ContractAdminUpdateResponse {
+            updated,
+            skipped,
+            cursor,
+        })?))
+}
@@ -0,0 +1,153 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Env, Event, Order, Response, StdResult, Storage, Uint64, WasmMsg,
+};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    execute::client::{
+        create::set_builtin_index_entries,
+        delete::{delete_from_groups, delete_from_relationships, delete_from_tags},
+        update::set_custom_index_entries,
+    },
+    lifecycle::{LifecycleArgs, LifecycleExecuteMsg, LifecycleExecuteMsgEnvelope},
+    models::ContractTombstone,
+    state::{
+        ensure_allowed_by_acl, ensure_contract_not_suspended, load_contract_group_ids,
+        load_contract_id, set_contract_expiry, ContractID, ARCHIVED_ADDR_2_ID, CONTRACT_ADDR_2_ID,
+        CONTRACT_DYN_METADATA, CONTRACT_EXPIRY, CONTRACT_ID_2_ADDR, CONTRACT_METADATA,
+        CONTRACT_TAGS, CONTRACT_TOMBSTONES, CONTRACT_USES_LIFECYCLE_HOOKS, IX_TAG, PARTITION_SIZES,
+        REL_ID_2_ADDR,
+    },
+};
+
+/// Remove a contract from all active indices, keeping its data under a
+/// tombstone so that it can be restored later. Custom index values, flags and
+/// suspensions are left in place, keyed by the contract's ID.
+pub fn on_execute(
+    ctx: Context,
+    contract_addr: Addr,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, env } = ctx;
+    let action = "archive";
+
+    deps.api.addr_validate(contract_addr.as_str())?;
+
+    let contract_id = load_contract_id(deps.storage, &contract_addr)?;
+    let mut resp = Response::new().add_attribute("action", action);
+
+    // If sender isn't the contract itself, only allow sender if auth'd by owner
+    // address or ACL.
+    if contract_addr != info.sender {
+        ensure_allowed_by_acl(&deps, &info.sender, "/table/archive")?;
+    } else {
+        ensure_contract_not_suspended(deps.storage, contract_id)?;
+    };
+
+    if let Some(msg) = archive_contract(
+        deps.storage,
+        &env,
+        &info.sender,
+        &contract_addr,
+        contract_id,
+    )? {
+        resp = resp.add_message(msg);
+    }
+
+    Ok(resp.add_event(
+        Event::new("archive")
+            .add_attribute("contract_address", contract_addr.to_string())
+            .add_attribute("contract_id", contract_id.to_string()),
+    ))
+}
+
+/// Move a contract's data under a tombstone, returning the lifecycle Suspend
+/// msg to send to it, if it uses lifecycle hooks.
+pub fn archive_contract(
+    storage: &mut dyn Storage,
+    env: &Env,
+    initiator: &Addr,
+    contract_addr: &Addr,
+    contract_id: ContractID,
+) -> Result<Option<WasmMsg>, ContractError> {
+    let meta = CONTRACT_METADATA.load(storage, contract_id)?;
+    let p = meta.partition;
+
+    // Collect everything needed to restore the contract before removing it
+    let mut tags: Vec<(String, u8)> = Vec::with_capacity(4);
+    for result in CONTRACT_TAGS
+        .prefix(contract_id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<Vec<StdResult<_>>>()
+    {
+        let tag = result?;
+        let uniqueness = IX_TAG.load(storage, (p, &tag, contract_id))?;
+        tags.push((tag, uniqueness));
+    }
+
+    let relationships = REL_ID_2_ADDR
+        .sub_prefix(contract_id)
+        .range(storage, None, None, Order::Ascending)
+        .map(|r| r.map(|((name, addr), uniqueness)| (name, addr, uniqueness)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let tombstone = ContractTombstone {
+        address: contract_addr.clone(),
+        meta: meta.clone(),
+        dyn_meta: CONTRACT_DYN_METADATA.may_load(storage, contract_id)?,
+        expiry: CONTRACT_EXPIRY.may_load(storage, contract_id)?,
+        groups: load_contract_group_ids(storage, contract_id)?,
+        tags,
+        relationships,
+        archived_at: env.block.time,
+        archived_by: initiator.clone(),
+    };
+
+    // Remove from active indices, tags, relationships and groups
+    set_builtin_index_entries(storage, p, contract_id, false)?;
+    set_custom_index_entries(storage, p, contract_id, false)?;
+    delete_from_tags(storage, contract_id)?;
+    delete_from_relationships(storage, contract_id)?;
+    delete_from_groups(storage, contract_id)?;
+
+    // Remove metadata and mappings between contract ID <-> contract Addr, so
+    // that the contract is no longer treated as part of the table.
+    CONTRACT_METADATA.remove(storage, contract_id);
+    CONTRACT_DYN_METADATA.remove(storage, contract_id);
+    CONTRACT_ID_2_ADDR.remove(storage, contract_id);
+    CONTRACT_ADDR_2_ID.remove(storage, contract_addr);
+
+    PARTITION_SIZES.update(storage, p, |maybe_n| -> Result<_, ContractError> {
+        maybe_n
+            .unwrap_or_default()
+            .checked_sub(Uint64::one())
+            .map_err(|e| ContractError::UnexpectedError {
+                reason: e.to_string(),
+            })
+    })?;
+
+    set_contract_expiry(storage, contract_id, None)?;
+
+    CONTRACT_TOMBSTONES.save(storage, contract_id, &tombstone)?;
+    ARCHIVED_ADDR_2_ID.save(storage, contract_addr, &contract_id.into())?;
+
+    Ok(
+        if CONTRACT_USES_LIFECYCLE_HOOKS
+            .may_load(storage, contract_id)?
+            .unwrap_or_default()
+        {
+            Some(WasmMsg::Execute {
+                contract_addr: contract_addr.clone().into(),
+                msg: to_json_binary(&LifecycleExecuteMsgEnvelope::Lifecycle(
+                    LifecycleExecuteMsg::Suspend(LifecycleArgs {
+                        table: env.contract.address.clone(),
+                        initiator: initiator.clone(),
+                    }),
+                ))?,
+                funds: vec![],
+            })
+        } else {
+            None
+        },
+    )
+}
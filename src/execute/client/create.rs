@@ -1,6 +1,6 @@
 use cosmwasm_std::{
-    attr, to_json_binary, Addr, DepsMut, Env, Event, Reply, Response, StdResult, Storage, SubMsg,
-    Uint64, WasmMsg,
+    attr, to_json_binary, Addr, Api, Coin, DepsMut, Env, Event, Reply, Response, StdResult,
+    Storage, SubMsg, Uint64, WasmMsg,
 };
 use cw_lib::utils::state::increment;
 
@@ -8,39 +8,90 @@ use crate::{
     context::Context,
     ensure::ensure_authorized_code_id,
     error::ContractError,
+    execute::client::update::{
+        ensure_required_index_values, ensure_valid_index_updates, update_indices,
+        update_relationships, update_tags,
+    },
     lifecycle::{LifecycleExecuteMsg, LifecycleExecuteMsgEnvelope, LifecycleSetupArgs},
-    models::{ContractMetadata, ReplyJob},
-    msg::CreationParams,
+    models::{ContractMetadata, FailedReplyJob, ReplyJob},
+    msg::{
+        BatchCreationResponse, BatchCreationResult, CreationParams, CreationResponse,
+        RelationshipUpdates, TagUpdate, TagUpdates,
+    },
     state::{
         append_group, ensure_allowed_by_acl, ensure_contract_not_suspended,
-        exists_contract_address, load_contract_id, load_next_contract_id, resolve_partition_id,
-        CONTRACT_METADATA, CONTRACT_USES_LIFECYCLE_HOOKS, IX_CODE_ID, IX_CONTRACT_ID,
-        IX_CREATED_AT, IX_CREATED_BY, IX_REV, IX_UPDATED_AT, IX_UPDATED_BY, PARTITION_SIZES,
-        REPLY_JOBS, REPLY_JOB_ID_COUNTER, X,
+        exists_contract_address, load_contract_id, load_next_contract_id, predict_contract_address,
+        resolve_partition_id, set_contract_expiry, ContractID, PartitionID, CONFIG_STR_MAX_LEN,
+        CONTRACT_DYN_METADATA, CONTRACT_METADATA, CONTRACT_USES_LIFECYCLE_HOOKS, CREATE_BATCHES,
+        FAILED_REPLY_JOBS, IX_CODE_ID, IX_CONTRACT_ID, IX_CREATED_AT, IX_CREATED_BY, IX_MANAGED,
+        IX_REV, IX_UPDATED_AT, IX_UPDATED_BY, PARTITION_SIZES, REPLY_JOBS, REPLY_JOB_ID_COUNTER, X,
     },
+    transaction::StorageTransaction,
 };
 
 pub fn on_execute(
     ctx: Context,
     params: CreationParams,
 ) -> Result<Response, ContractError> {
-    let Context { deps, info, env } = ctx;
+    let Context {
+        mut deps,
+        info,
+        env,
+    } = ctx;
     let action = "create";
 
-    ensure_authorized_code_id(deps.storage, params.code_id.into())?;
+    ensure_authorized_creator(&deps, &info.sender)?;
+
+    let (submsg, job_id, maybe_contract_addr) =
+        build_create_submsg(&mut deps, &env, params, &info.sender, info.funds, None)?;
+
+    let mut resp = Response::new().add_attributes(vec![
+        attr("action", action),
+        attr("job_id", job_id.to_string()),
+    ]);
+
+    if let Some(contract_addr) = maybe_contract_addr {
+        resp = resp.add_attribute("contract_address", contract_addr.to_string());
+    }
 
-    // If sender isn't the contract itself, only allow sender if auth'd by owner
-    // address or ACL.
-    if !exists_contract_address(deps.storage, &info.sender) {
-        ensure_allowed_by_acl(&deps, &info.sender, "/table/create")?;
+    Ok(resp.add_submessage(submsg))
+}
+
+/// If sender isn't a contract in the table, only allow sender if auth'd by
+/// owner address or ACL.
+pub fn ensure_authorized_creator(
+    deps: &DepsMut,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if !exists_contract_address(deps.storage, sender) {
+        ensure_allowed_by_acl(deps, sender, "/table/create")?;
     } else {
-        let sender_contract_id = load_contract_id(deps.storage, &info.sender)?;
+        let sender_contract_id = load_contract_id(deps.storage, sender)?;
         ensure_contract_not_suspended(deps.storage, sender_contract_id)?;
     }
+    Ok(())
+}
+
+/// Save a reply job for the given creation params and build the instantiate
+/// submsg that triggers it. Returns the submsg, its job ID and, if a salt was
+/// given, the address of the contract to be created.
+pub fn build_create_submsg(
+    deps: &mut DepsMut,
+    env: &Env,
+    params: CreationParams,
+    initiator: &Addr,
+    funds: Vec<Coin>,
+    batch_id: Option<u64>,
+) -> Result<(SubMsg, u64, Option<Addr>), ContractError> {
+    ensure_authorized_code_id(deps.storage, params.code_id.into())?;
 
-    let initiator = &info.sender;
-    let job_id = create_reply_job(deps.storage, &params, initiator)?;
-    let admin: Option<String> = Some(params.admin.unwrap_or(env.contract.address).into());
+    // Validate initial index values before instantiating anything
+    let values = params.values.as_deref().unwrap_or_default();
+    ensure_valid_index_updates(deps.api, deps.storage, values)?;
+    ensure_required_index_values(deps.storage, values)?;
+
+    let job_id = create_reply_job(deps.storage, &params, initiator, batch_id)?;
+    let admin: Option<String> = Some(params.admin.unwrap_or(env.contract.address.clone()).into());
     // let maybe_table_name = TABLE_INFO.load(deps.storage)?.name;
     let label = params.label.unwrap_or_else(|| {
         format!(
@@ -52,32 +103,62 @@ pub fn on_execute(
         .to_owned()
     });
 
-    Ok(Response::new()
-        .add_attributes(vec![
-            attr("action", action),
-            attr("job_id", job_id.to_string()),
-        ])
-        .add_submessage(SubMsg::reply_always(
+    // Use instantiate2 if a salt is given, so that the address of the new
+    // contract is known upfront.
+    let (instantiate_msg, maybe_contract_addr) = if let Some(salt) = params.salt {
+        let contract_addr = predict_contract_address(
+            deps.as_ref(),
+            &env.contract.address,
+            params.code_id.into(),
+            &salt,
+        )?;
+        if exists_contract_address(deps.storage, &contract_addr) {
+            return Err(ContractError::CreateError {
+                reason: format!("contract address {} already exists", contract_addr),
+            });
+        }
+        (
+            WasmMsg::Instantiate2 {
+                code_id: params.code_id.into(),
+                msg: params.instantiate_msg,
+                funds,
+                admin,
+                label,
+                salt,
+            },
+            Some(contract_addr),
+        )
+    } else {
+        (
             WasmMsg::Instantiate {
                 code_id: params.code_id.into(),
-                msg: params.instantiate_msg.clone(),
-                funds: info.funds,
+                msg: params.instantiate_msg,
+                funds,
                 admin,
                 label,
             },
-            job_id,
-        )))
+            None,
+        )
+    };
+
+    Ok((
+        SubMsg::reply_always(instantiate_msg, job_id),
+        job_id,
+        maybe_contract_addr,
+    ))
 }
 
 fn create_reply_job(
     storage: &mut dyn Storage,
     msg: &CreationParams,
     initiator: &Addr,
+    batch_id: Option<u64>,
 ) -> Result<u64, ContractError> {
     let job_id: u64 = increment(storage, &REPLY_JOB_ID_COUNTER, Uint64::one())?.into();
     let job = ReplyJob::Create {
         params: msg.clone(),
         initiator: initiator.clone(),
+        batch_id,
     };
     REPLY_JOBS.save(storage, job_id, &job)?;
     Ok(job_id)
@@ -89,104 +170,378 @@ pub fn on_reply(
     reply: Reply,
     params: CreationParams,
     initiator: Addr,
+    batch_id: Option<u64>,
 ) -> Result<Response, ContractError> {
     let mut resp = Response::new();
 
-    match &reply.result {
-        cosmwasm_std::SubMsgResult::Ok(subcall_resp) => {
-            if let Some(e) = subcall_resp.events.iter().find(|e| e.ty == "instantiate") {
-                if let Some(attr) = e
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key == "_contract_address")
-                {
-                    let contract_addr = Addr::unchecked(attr.value.to_string());
-                    let contract_id = load_next_contract_id(deps.storage, &contract_addr)?;
-                    let p = resolve_partition_id(deps.storage, params.partition)?;
-
-                    // init creation-time contract metadata
-                    let metadata = ContractMetadata {
-                        id: contract_id.into(),
-                        is_managed: params.admin.is_none(),
-                        created_at_height: env.block.height.into(),
-                        created_at: env.block.time,
-                        created_by: initiator.clone(),
-                        code_id: params.code_id.into(),
-                        partition: p,
-                    };
-
-                    CONTRACT_METADATA.save(deps.storage, contract_id, &metadata)?;
-
-                    let use_lifecycle_hooks = params.use_lifecycle_hooks.unwrap_or_default();
-
-                    CONTRACT_USES_LIFECYCLE_HOOKS.save(
-                        deps.storage,
-                        contract_id,
-                        &use_lifecycle_hooks,
-                    )?;
-
-                    PARTITION_SIZES.update(deps.storage, p, |maybe_n| -> StdResult<_> {
-                        Ok(maybe_n.unwrap_or_default() + Uint64::one())
-                    })?;
-
-                    IX_CONTRACT_ID.save(deps.storage, (p, contract_id, contract_id), &X)?;
-                    IX_CODE_ID.save(deps.storage, (p, params.code_id.into(), contract_id), &X)?;
-                    IX_REV.save(deps.storage, (p, 1, contract_id), &X)?;
-                    IX_CREATED_BY.save(
-                        deps.storage,
-                        (p, initiator.to_string(), contract_id),
-                        &X,
-                    )?;
-                    IX_UPDATED_BY.save(
-                        deps.storage,
-                        (p, initiator.to_string(), contract_id),
-                        &X,
-                    )?;
-                    IX_CREATED_AT.save(
-                        deps.storage,
-                        (p, env.block.time.nanos(), contract_id),
-                        &X,
-                    )?;
-                    IX_UPDATED_AT.save(
-                        deps.storage,
-                        (p, env.block.time.nanos(), contract_id),
-                        &X,
-                    )?;
-
-                    if let Some(group_ids) = params.groups {
-                        for group_id in group_ids.iter() {
-                            append_group(deps.storage, *group_id, contract_id)?;
-                        }
-                    }
-
-                    resp = resp.add_event(
-                        Event::new("post_create")
-                            .add_attribute("contract_address", contract_addr.to_string())
-                            .add_attribute("contract_id", contract_id.to_string()),
-                    );
-
-                    if use_lifecycle_hooks {
-                        resp = resp.add_message(WasmMsg::Execute {
-                            contract_addr: contract_addr.into(),
-                            msg: to_json_binary(&LifecycleExecuteMsgEnvelope::Lifecycle(
-                                LifecycleExecuteMsg::Setup(LifecycleSetupArgs {
-                                    table: env.contract.address.clone(),
-                                    initiator,
-                                    id: contract_id.to_string(),
-                                }),
-                            ))?,
-                            funds: vec![],
-                        });
-                    }
-                }
-            }
-        },
-        cosmwasm_std::SubMsgResult::Err(err_reason) => {
-            return Err(ContractError::CreateError {
-                reason: err_reason.into(),
-            });
+    // In best-effort batches, report failures instead of reverting the other
+    // contracts created in the batch.
+    let is_best_effort = match batch_id {
+        Some(batch_id) => CREATE_BATCHES.load(deps.storage, batch_id)?.best_effort,
+        None => false,
+    };
+
+    let contract_addr = match &reply.result {
+        cosmwasm_std::SubMsgResult::Ok(subcall_resp) => subcall_resp
+            .events
+            .iter()
+            .filter(|e| e.ty == "instantiate")
+            .flat_map(|e| e.attributes.iter())
+            .find(|attr| attr.key == "_contract_address")
+            .map(|attr| Addr::unchecked(attr.value.to_string()))
+            .ok_or_else(|| ContractError::CreateError {
+                reason: "instantiated contract address not found".to_owned(),
+            }),
+        cosmwasm_std::SubMsgResult::Err(err_reason) => Err(ContractError::CreateError {
+            reason: err_reason.clone(),
+        }),
+    };
+
+    let contract_addr = match contract_addr {
+        Ok(contract_addr) => contract_addr,
+        Err(err) if is_best_effort => {
+            let reason = match err {
+                ContractError::CreateError { reason } => reason,
+                err => err.to_string(),
+            };
+            return fail_batch_item(
+                deps.storage,
+                &env,
+                reply.id,
+                params,
+                initiator,
+                batch_id,
+                None,
+                reason,
+            );
         },
+        Err(err) => return Err(err),
+    };
+
+    // Register the new contract. In best-effort batches, the registration is
+    // discarded as a whole if any step fails, e.g. a unique index conflict
+    // with another item in the batch, leaving the new contract unregistered.
+    let contract_id = if is_best_effort {
+        let mut tx = StorageTransaction::new(deps.storage);
+        let result =
+            register_created_contract(deps.api, &mut tx, &env, &contract_addr, &params, &initiator);
+        match result {
+            Ok(contract_id) => {
+                tx.commit();
+                contract_id
+            },
+            Err(err) => {
+                drop(tx);
+                return fail_batch_item(
+                    deps.storage,
+                    &env,
+                    reply.id,
+                    params,
+                    initiator,
+                    batch_id,
+                    Some(contract_addr),
+                    err.to_string(),
+                );
+            },
+        }
+    } else {
+        register_created_contract(
+            deps.api,
+            deps.storage,
+            &env,
+            &contract_addr,
+            &params,
+            &initiator,
+        )?
+    };
+
+    resp = resp.add_event(
+        Event::new("post_create")
+            .add_attribute("contract_address", contract_addr.to_string())
+            .add_attribute("contract_id", contract_id.to_string()),
+    );
+
+    if params.use_lifecycle_hooks.unwrap_or_default() {
+        resp = resp.add_message(build_setup_msg(
+            &env,
+            &contract_addr,
+            &initiator,
+            contract_id,
+        )?);
     }
 
+    // Expose the new contract as response data, for use in the caller's own
+    // reply handler. For batches, the data set by the batch's final reply
+    // contains the results of all items.
+    resp = resp.set_data(if let Some(batch_id) = batch_id {
+        to_json_binary(&push_batch_result(
+            deps.storage,
+            batch_id,
+            Some(contract_addr),
+            Some(contract_id.into()),
+            None,
+        )?)?
+    } else {
+        to_json_binary(&CreationResponse {
+            address: contract_addr,
+            id: contract_id.into(),
+        })?
+    });
+
     Ok(resp)
 }
+
+/// Insert a newly instantiated contract into the table, applying its initial
+/// groups, index values, tags, relationships and expiry.
+fn register_created_contract(
+    api: &dyn Api,
+    storage: &mut dyn Storage,
+    env: &Env,
+    contract_addr: &Addr,
+    params: &CreationParams,
+    initiator: &Addr,
+) -> Result<ContractID, ContractError> {
+    let p = resolve_partition_id(storage, params.partition.clone())?;
+    let contract_id = insert_contract(
+        storage,
+        env,
+        contract_addr,
+        params.code_id.into(),
+        initiator,
+        p,
+        Some(params.admin.clone().unwrap_or(env.contract.address.clone())),
+    )?;
+
+    let use_lifecycle_hooks = params.use_lifecycle_hooks.unwrap_or_default();
+
+    CONTRACT_USES_LIFECYCLE_HOOKS.save(storage, contract_id, &use_lifecycle_hooks)?;
+
+    if let Some(group_ids) = &params.groups {
+        for group_id in group_ids.iter() {
+            append_group(storage, *group_id, contract_id)?;
+        }
+    }
+
+    // Apply initial index values, tags and relationships, using the same rules
+    // as a regular update.
+    let max_str_len = CONFIG_STR_MAX_LEN.load(storage)? as usize;
+
+    if let Some(values) = params.values.clone() {
+        update_indices(api, storage, p, contract_id, values)?;
+    }
+
+    let tags: Vec<TagUpdate> = params
+        .tags
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|text| TagUpdate { text, unique: None })
+        .chain(params.tag_options.clone().unwrap_or_default())
+        .collect();
+    if !tags.is_empty() {
+        update_tags(
+            storage,
+            p,
+            contract_id,
+            TagUpdates {
+                add: Some(tags),
+                remove: None,
+            },
+            max_str_len,
+        )?;
+    }
+
+    if let Some(rels) = params.relationships.clone() {
+        update_relationships(
+            storage,
+            contract_id,
+            RelationshipUpdates {
+                add: Some(rels),
+                remove: None,
+            },
+            max_str_len,
+        )?;
+    }
+
+    if let Some(expiry) = params.expiry.clone() {
+        set_contract_expiry(storage, contract_id, Some(expiry))?;
+    }
+
+    Ok(contract_id)
+}
+
+/// Record the failure of one item in a best-effort batch, along with the
+/// address of its contract if it was instantiated but couldn't be registered.
+#[allow(clippy::too_many_arguments)]
+fn fail_batch_item(
+    storage: &mut dyn Storage,
+    env: &Env,
+    job_id: u64,
+    params: CreationParams,
+    initiator: Addr,
+    batch_id: Option<u64>,
+    maybe_contract_addr: Option<Addr>,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let batch_id = batch_id.ok_or_else(|| ContractError::UnexpectedError {
+        reason: format!("reply job {} is not part of a batch", job_id),
+    })?;
+    FAILED_REPLY_JOBS.save(
+        storage,
+        job_id,
+        &FailedReplyJob {
+            job: ReplyJob::Create {
+                params,
+                initiator,
+                batch_id: Some(batch_id),
+            },
+            reason: reason.clone(),
+            failed_at: env.block.time,
+        },
+    )?;
+    let batch_resp = push_batch_result(
+        storage,
+        batch_id,
+        maybe_contract_addr.clone(),
+        None,
+        Some(reason.clone()),
+    )?;
+    let mut event = Event::new("create_failed")
+        .add_attribute("batch_id", batch_id.to_string())
+        .add_attribute("job_id", job_id.to_string())
+        .add_attribute("reason", reason);
+    if let Some(contract_addr) = maybe_contract_addr {
+        event = event.add_attribute("contract_address", contract_addr.to_string());
+    }
+    Ok(Response::new()
+        .add_event(event)
+        .set_data(to_json_binary(&batch_resp)?))
+}
+
+/// Append the result of one item in a batch, returning all results so far. The
+/// batch is removed from state once all of its items have been processed.
+fn push_batch_result(
+    storage: &mut dyn Storage,
+    batch_id: u64,
+    address: Option<Addr>,
+    id: Option<Uint64>,
+    error: Option<String>,
+) -> Result<BatchCreationResponse, ContractError> {
+    let mut batch = CREATE_BATCHES.load(storage, batch_id)?;
+
+    batch.results.push(BatchCreationResult {
+        index: batch.results.len() as u32,
+        address,
+        id,
+        error,
+    });
+
+    if batch.results.len() as u32 >= batch.size {
+        CREATE_BATCHES.remove(storage, batch_id);
+    } else {
+        CREATE_BATCHES.save(storage, batch_id, &batch)?;
+    }
+
+    Ok(BatchCreationResponse {
+        results: batch.results,
+    })
+}
+
+/// Allocate a new contract ID for the given address, saving its creation-time
+/// metadata and inserting it into each built-in index.
+pub fn insert_contract(
+    storage: &mut dyn Storage,
+    env: &Env,
+    contract_addr: &Addr,
+    code_id: u64,
+    initiator: &Addr,
+    p: PartitionID,
+    admin: Option<Addr>,
+) -> Result<ContractID, ContractError> {
+    let contract_id = load_next_contract_id(storage, contract_addr)?;
+    let is_managed = admin == Some(env.contract.address.clone());
+
+    // init creation-time contract metadata
+    let metadata = ContractMetadata {
+        id: contract_id.into(),
+        is_managed,
+        admin,
+        created_at_height: env.block.height.into(),
+        created_at: env.block.time,
+        created_by: initiator.clone(),
+        code_id: code_id.into(),
+        partition: p,
+    };
+
+    CONTRACT_METADATA.save(storage, contract_id, &metadata)?;
+
+    PARTITION_SIZES.update(storage, p, |maybe_n| -> StdResult<_> {
+        Ok(maybe_n.unwrap_or_default() + Uint64::one())
+    })?;
+
+    set_builtin_index_entries(storage, p, contract_id, true)?;
+
+    Ok(contract_id)
+}
+
+/// Insert or remove a contract's entries in each built-in index. Until a
+/// contract's first update, its creation-time values are used as its "updated"
+/// values.
+pub fn set_builtin_index_entries(
+    storage: &mut dyn Storage,
+    p: PartitionID,
+    contract_id: ContractID,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let meta = CONTRACT_METADATA.load(storage, contract_id)?;
+    let (rev, updated_by, updated_at) =
+        match CONTRACT_DYN_METADATA.may_load(storage, contract_id)? {
+            Some(dyn_meta) => (dyn_meta.rev.u64(), dyn_meta.updated_by, dyn_meta.updated_at),
+            None => (1, meta.created_by.clone(), meta.created_at),
+        };
+
+    let code_id: u64 = meta.code_id.into();
+    let is_managed: u8 = meta.is_managed.into();
+
+    if is_insert {
+        IX_CONTRACT_ID.save(storage, (p, contract_id, contract_id), &X)?;
+        IX_CODE_ID.save(storage, (p, code_id, contract_id), &X)?;
+        IX_REV.save(storage, (p, rev, contract_id), &X)?;
+        IX_CREATED_BY.save(storage, (p, meta.created_by.to_string(), contract_id), &X)?;
+        IX_UPDATED_BY.save(storage, (p, updated_by.to_string(), contract_id), &X)?;
+        IX_CREATED_AT.save(storage, (p, meta.created_at.nanos(), contract_id), &X)?;
+        IX_UPDATED_AT.save(storage, (p, updated_at.nanos(), contract_id), &X)?;
+        IX_MANAGED.save(storage, (p, is_managed, contract_id), &X)?;
+    } else {
+        IX_CONTRACT_ID.remove(storage, (p, contract_id, contract_id));
+        IX_CODE_ID.remove(storage, (p, code_id, contract_id));
+        IX_REV.remove(storage, (p, rev, contract_id));
+        IX_CREATED_BY.remove(storage, (p, meta.created_by.to_string(), contract_id));
+        IX_UPDATED_BY.remove(storage, (p, updated_by.to_string(), contract_id));
+        IX_CREATED_AT.remove(storage, (p, meta.created_at.nanos(), contract_id));
+        IX_UPDATED_AT.remove(storage, (p, updated_at.nanos(), contract_id));
+        IX_MANAGED.remove(storage, (p, is_managed, contract_id));
+    }
+
+    Ok(())
+}
+
+/// Build the lifecycle Setup msg sent to a contract when it enters the table.
+pub fn build_setup_msg(
+    env: &Env,
+    contract_addr: &Addr,
+    initiator: &Addr,
+    contract_id: ContractID,
+) -> Result<WasmMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: contract_addr.into(),
+        msg: to_json_binary(&LifecycleExecuteMsgEnvelope::Lifecycle(
+            LifecycleExecuteMsg::Setup(LifecycleSetupArgs {
+                table: env.contract.address.clone(),
+                initiator: initiator.clone(),
+                id: contract_id.to_string(),
+            }),
+        ))?,
+        funds: vec![],
+    })
+}
@@ -0,0 +1,63 @@
+use cosmwasm_std::{attr, Response};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    execute::client::create::{build_create_submsg, ensure_authorized_creator},
+    msg::{BatchCreationParams, BatchMode},
+    state::create_batch,
+};
+
+/// Instantiate and index many contracts in one msg. In atomic mode, the whole
+/// batch reverts if any contract fails. In best-effort mode, failures are
+/// reported in the response data instead.
+pub fn on_execute(
+    ctx: Context,
+    params: BatchCreationParams,
+) -> Result<Response, ContractError> {
+    let Context {
+        mut deps,
+        info,
+        env,
+    } = ctx;
+    let action = "create_many";
+
+    ensure_authorized_creator(&deps, &info.sender)?;
+
+    if params.items.is_empty() {
+        return Err(ContractError::ValidationError {
+            reason: "batch must contain at least one item".to_owned(),
+        });
+    }
+
+    // There's no unambiguous way to split funds between items, so don't accept
+    // any.
+    if !info.funds.is_empty() {
+        return Err(ContractError::ValidationError {
+            reason: "funds are not accepted when creating contracts in batch".to_owned(),
+        });
+    }
+
+    let best_effort = params.mode == Some(BatchMode::BestEffort);
+    let batch_id = create_batch(deps.storage, best_effort, params.items.len() as u32)?;
+
+    let mut resp = Response::new().add_attributes(vec![
+        attr("action", action),
+        attr("batch_id", batch_id.to_string()),
+    ]);
+
+    for item in params.items {
+        let (submsg, job_id, maybe_contract_addr) =
+            build_create_submsg(&mut deps, &env, item, &info.sender, vec![], Some(batch_id))?;
+
+        resp = resp.add_attribute("job_id", job_id.to_string());
+
+        if let Some(contract_addr) = maybe_contract_addr {
+            resp = resp.add_attribute("contract_address", contract_addr.to_string());
+        }
+
+        resp = resp.add_submessage(submsg);
+    }
+
+    Ok(resp)
+}
@@ -1,22 +1,23 @@
-use std::marker::PhantomData;
-
-use cosmwasm_std::{to_json_binary, Addr, Order, Response, StdResult, Storage, Uint64, WasmMsg};
-use cw_storage_plus::{Bound, Deque};
+use cosmwasm_std::{
+    to_json_binary, Addr, Env, Order, Response, StdResult, Storage, Uint64, WasmMsg,
+};
+use cw_storage_plus::Deque;
 
 use crate::{
     context::Context,
     error::ContractError,
+    execute::client::{
+        create::set_builtin_index_entries,
+        update::{remove_index_value, set_custom_index_entries, set_group_aggregate_entries},
+    },
     lifecycle::{LifecycleArgs, LifecycleExecuteMsg, LifecycleExecuteMsgEnvelope},
     models::ContractFlag,
-    msg::IndexType,
     state::{
         ensure_allowed_by_acl, ensure_contract_not_suspended, load_contract_id, remove_from_group,
-        ContractID, CONTRACT_ADDR_2_ID, CONTRACT_DYN_METADATA, CONTRACT_GROUP_IDS,
-        CONTRACT_ID_2_ADDR, CONTRACT_INDEX_TYPES, CONTRACT_METADATA, CONTRACT_SUSPENSIONS,
-        CONTRACT_TAGS, CONTRACT_USES_LIFECYCLE_HOOKS, IX_CODE_ID, IX_CONTRACT_ID, IX_CREATED_AT,
-        IX_CREATED_BY, IX_REV, IX_TAG, IX_UPDATED_AT, IX_UPDATED_BY, PARTITION_SIZES,
-        PARTITION_TAG_COUNTS, REL_ADDR_2_ID, REL_ID_2_ADDR, VALUES_BINARY, VALUES_BOOL, VALUES_I32,
-        VALUES_STRING, VALUES_TIME, VALUES_U128, VALUES_U16, VALUES_U32, VALUES_U64, VALUES_U8,
+        set_contract_expiry, ContractID, CONTRACT_ADDR_2_ID, CONTRACT_DYN_METADATA,
+        CONTRACT_GROUP_IDS, CONTRACT_ID_2_ADDR, CONTRACT_INDEX_TYPES, CONTRACT_METADATA,
+        CONTRACT_SUSPENSIONS, CONTRACT_TAGS, CONTRACT_USES_LIFECYCLE_HOOKS, IX_TAG,
+        PARTITION_SIZES, PARTITION_TAG_COUNTS, REL_ADDR_2_ID, REL_ID_2_ADDR, VALUES_COMPOUND,
     },
 };
 
@@ -41,32 +42,57 @@ pub fn on_execute(
         ensure_contract_not_suspended(deps.storage, contract_id)?;
     };
 
-    if CONTRACT_USES_LIFECYCLE_HOOKS
-        .may_load(deps.storage, contract_id)?
+    if let Some(msg) = delete_contract(
+        deps.storage,
+        &env,
+        &info.sender,
+        &contract_addr,
+        contract_id,
+    )? {
+        resp = resp.add_message(msg);
+    }
+
+    Ok(resp)
+}
+
+/// Remove every trace of a contract from the table, returning the lifecycle
+/// Teardown msg to send to it, if it uses lifecycle hooks.
+pub fn delete_contract(
+    storage: &mut dyn Storage,
+    env: &Env,
+    initiator: &Addr,
+    contract_addr: &Addr,
+    contract_id: ContractID,
+) -> Result<Option<WasmMsg>, ContractError> {
+    let maybe_msg = if CONTRACT_USES_LIFECYCLE_HOOKS
+        .may_load(storage, contract_id)?
         .unwrap_or_default()
     {
-        resp = resp.add_message(WasmMsg::Execute {
+        Some(WasmMsg::Execute {
             contract_addr: contract_addr.clone().into(),
             msg: to_json_binary(&LifecycleExecuteMsgEnvelope::Lifecycle(
                 LifecycleExecuteMsg::Teardown(LifecycleArgs {
                     table: env.contract.address.clone(),
-                    initiator: info.sender.clone(),
+                    initiator: initiator.clone(),
                 }),
             ))?,
             funds: vec![],
-        });
-    }
+        })
+    } else {
+        None
+    };
 
-    delete_from_indices(deps.storage, contract_id)?;
-    delete_from_tags(deps.storage, contract_id)?;
-    delete_from_relationships(deps.storage, contract_id)?;
-    delete_from_partition(deps.storage, &contract_addr, contract_id)?;
-    delete_from_groups(deps.storage, contract_id)?;
+    delete_from_indices(storage, contract_id)?;
+    delete_from_tags(storage, contract_id)?;
+    delete_from_relationships(storage, contract_id)?;
+    delete_from_partition(storage, contract_addr, contract_id)?;
+    delete_from_groups(storage, contract_id)?;
+    set_contract_expiry(storage, contract_id, None)?;
 
-    Ok(resp)
+    Ok(maybe_msg)
 }
 
-fn delete_from_groups(
+pub fn delete_from_groups(
     storage: &mut dyn Storage,
     contract_id: ContractID,
 ) -> Result<(), ContractError> {
@@ -77,6 +103,7 @@ fn delete_from_groups(
     {
         let group_id = maybe_group_id?;
         remove_from_group(storage, group_id, contract_id)?;
+        set_group_aggregate_entries(storage, group_id, contract_id, false)?;
     }
     Ok(())
 }
@@ -124,7 +151,7 @@ fn delete_from_partition(
     Ok(())
 }
 
-fn delete_from_tags(
+pub fn delete_from_tags(
     storage: &mut dyn Storage,
     id: ContractID,
 ) -> Result<(), ContractError> {
@@ -140,6 +167,7 @@ fn delete_from_tags(
 
         // Clear index used for finding contract by tags
         IX_TAG.remove(storage, (p, &tag, id));
+        CONTRACT_TAGS.remove(storage, (id, tag.clone()));
 
         // Decrement the global counts for each tag removed (in the contract's current partition)
         PARTITION_TAG_COUNTS.update(storage, (p, &tag), |maybe_n| -> Result<_, ContractError> {
@@ -169,67 +197,45 @@ fn delete_from_indices(
     let p = meta.partition;
 
     // Remove from main metadata indices
-    IX_CONTRACT_ID.remove(storage, (p, id, id));
-    IX_CODE_ID.remove(storage, (p, meta.code_id.into(), id));
-    IX_CREATED_AT.remove(storage, (p, meta.created_at.nanos(), id));
-    IX_CREATED_BY.remove(storage, (p, meta.created_by.to_string(), id));
-
-    if let Some(up_meta) = CONTRACT_DYN_METADATA.may_load(storage, id)? {
-        // Remove from "update" metadata indices
-        IX_UPDATED_AT.remove(storage, (p, up_meta.updated_at.nanos(), id));
-        IX_UPDATED_BY.remove(storage, (p, up_meta.updated_by.to_string(), id));
-        IX_REV.remove(storage, (p, up_meta.rev.into(), id));
-    }
+    set_builtin_index_entries(storage, p, id, false)?;
 
     // Remove from custom indices
+    set_custom_index_entries(storage, p, id, false)?;
+
+    // Remove stored custom index values
     for result in CONTRACT_INDEX_TYPES
         .prefix(id)
         .range(storage, None, None, Order::Ascending)
         .collect::<Vec<StdResult<_>>>()
     {
         let (index_name, index_type) = result?;
+        remove_index_value(storage, id, &index_name, &index_type)?;
+    }
 
-        CONTRACT_INDEX_TYPES.remove(storage, (id, &index_name));
-
-        match index_type {
-            IndexType::String => VALUES_STRING.remove(storage, (id, &index_name)),
-            IndexType::Bool => VALUES_BOOL.remove(storage, (id, &index_name)),
-            IndexType::Timestamp => VALUES_TIME.remove(storage, (id, &index_name)),
-            IndexType::Int32 => VALUES_I32.remove(storage, (id, &index_name)),
-            IndexType::Uint8 => VALUES_U8.remove(storage, (id, &index_name)),
-            IndexType::Uint16 => VALUES_U16.remove(storage, (id, &index_name)),
-            IndexType::Uint32 => VALUES_U32.remove(storage, (id, &index_name)),
-            IndexType::Uint64 => VALUES_U64.remove(storage, (id, &index_name)),
-            IndexType::Uint128 => VALUES_U128.remove(storage, (id, &index_name)),
-            IndexType::Binary => VALUES_BINARY.remove(storage, (id, &index_name)),
-        }
+    // Remove stored compound index values
+    for result in VALUES_COMPOUND
+        .prefix(id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<Vec<StdResult<_>>>()
+    {
+        VALUES_COMPOUND.remove(storage, (id, &result?));
     }
 
     Ok(())
 }
 
-fn delete_from_relationships(
+pub fn delete_from_relationships(
     storage: &mut dyn Storage,
     id: ContractID,
 ) -> Result<(), ContractError> {
     for result in REL_ID_2_ADDR
-        .keys(
-            storage,
-            Some(Bound::Inclusive((
-                (id, "".to_owned(), "".to_owned()),
-                PhantomData,
-            ))),
-            None,
-            Order::Ascending,
-        )
+        .sub_prefix(id)
+        .keys(storage, None, None, Order::Ascending)
         .collect::<Vec<StdResult<_>>>()
     {
-        let (contract_id, rel_name, account_addr) = result?;
+        let (rel_name, account_addr) = result?;
 
-        REL_ID_2_ADDR.remove(
-            storage,
-            (contract_id, rel_name.clone(), account_addr.clone()),
-        );
+        REL_ID_2_ADDR.remove(storage, (id, rel_name.clone(), account_addr.clone()));
         REL_ADDR_2_ID.remove(storage, (account_addr, rel_name, id.to_string()));
     }
 
@@ -0,0 +1,69 @@
+use cosmwasm_std::{attr, Event, Response};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    execute::client::{archive::archive_contract, delete::delete_contract},
+    msg::{ExpiryAction, ExpiryCrankParams},
+    state::{load_contract_addr, load_expired_contract_ids, CONTRACT_EXPIRY},
+};
+
+/// Archive or delete a bounded batch of expired contracts, according to the
+/// action set in each contract's expiry settings. Anyone may crank this.
+pub fn on_execute(
+    ctx: Context,
+    params: ExpiryCrankParams,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, env } = ctx;
+    let action = "expire";
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let contract_ids = load_expired_contract_ids(deps.storage, &env.block, limit)?;
+
+    let mut resp = Response::new();
+
+    for contract_id in contract_ids.iter() {
+        let expiry = CONTRACT_EXPIRY.load(deps.storage, *contract_id)?;
+        let contract_addr = load_contract_addr(deps.storage, *contract_id)?;
+        let expiry_action = expiry.action.unwrap_or(ExpiryAction::Archive);
+
+        let maybe_msg = match expiry_action {
+            ExpiryAction::Archive => archive_contract(
+                deps.storage,
+                &env,
+                &info.sender,
+                &contract_addr,
+                *contract_id,
+            )?,
+            ExpiryAction::Delete => delete_contract(
+                deps.storage,
+                &env,
+                &info.sender,
+                &contract_addr,
+                *contract_id,
+            )?,
+        };
+
+        if let Some(msg) = maybe_msg {
+            resp = resp.add_message(msg);
+        }
+
+        resp = resp.add_event(
+            Event::new("expire")
+                .add_attribute("contract_address", contract_addr.to_string())
+                .add_attribute("contract_id", contract_id.to_string())
+                .add_attribute(
+                    "expiry_action",
+                    match expiry_action {
+                        ExpiryAction::Archive => "archive",
+                        ExpiryAction::Delete => "delete",
+                    },
+                ),
+        );
+    }
+
+    Ok(resp.add_attributes(vec![
+        attr("action", action),
+        attr("count", contract_ids.len().to_string()),
+    ]))
+}
@@ -1,4 +1,9 @@
+pub mod archive;
 pub mod create;
+pub mod create_many;
 pub mod delete;
+pub mod expire;
 pub mod flag;
+pub mod refresh;
+pub mod register;
 pub mod update;
@@ -0,0 +1,84 @@
+use cosmwasm_std::{attr, to_json_binary, Event, Response};
+
+use crate::{
+    context::Context,
+    error::ContractError,
+    msg::{RefreshParams, RefreshResponse},
+    state::{
+        detect_contract_drift, select_contract_ids, CONTRACT_METADATA, IX_CODE_ID, IX_MANAGED, X,
+    },
+};
+
+/// Sync the stored code ID and admin of a page of contracts with their
+/// on-chain contract info, as when they're migrated outside of the table.
+pub fn on_execute(
+    ctx: Context,
+    params: RefreshParams,
+) -> Result<Response, ContractError> {
+    let Context { deps, env, .. } = ctx;
+    let action = "refresh";
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let contract_ids = select_contract_ids(
+        deps.storage,
+        &params.select,
+        params.cursor.map(|c| c.u64()),
+        limit,
+    )?;
+
+    let mut resp = Response::new();
+    let mut refreshed: u32 = 0;
+
+    for contract_id in contract_ids.iter() {
+        let drift = match detect_contract_drift(deps.as_ref(), &env.contract.address, *contract_id)?
+        {
+            Some(drift) => drift,
+            None => continue,
+        };
+
+        let mut meta = CONTRACT_METADATA.load(deps.storage, *contract_id)?;
+        let p = meta.partition;
+
+        if drift.code_id != drift.stored_code_id {
+            IX_CODE_ID.remove(deps.storage, (p, meta.code_id.into(), *contract_id));
+            IX_CODE_ID.save(deps.storage, (p, drift.code_id.into(), *contract_id), &X)?;
+            meta.code_id = drift.code_id;
+        }
+
+        if drift.is_managed != meta.is_managed {
+            IX_MANAGED.remove(deps.storage, (p, meta.is_managed.into(), *contract_id));
+            IX_MANAGED.save(deps.storage, (p, drift.is_managed.into(), *contract_id), &X)?;
+            meta.is_managed = drift.is_managed;
+        }
+
+        meta.admin = drift.admin.clone();
+
+        CONTRACT_METADATA.save(deps.storage, *contract_id, &meta)?;
+
+        resp = resp.add_event(
+            Event::new("refresh")
+                .add_attribute("contract_address", drift.address.to_string())
+                .add_attribute("contract_id", contract_id.to_string())
+                .add_attribute("code_id", drift.code_id.to_string())
+                .add_attribute(
+                    "admin",
+                    drift.admin.map(|a| a.to_string()).unwrap_or_default(),
+                ),
+        );
+
+        refreshed += 1;
+    }
+
+    let cursor = if contract_ids.len() == limit {
+        contract_ids.last().map(|id| (*id).into())
+    } else {
+        None
+    };
+
+    Ok(resp
+        .add_attributes(vec![
+            attr("action", action),
+            attr("refreshed", refreshed.to_string()),
+        ])
+        .set_data(to_json_binary(&RefreshResponse { refreshed, cursor })?))
+}
@@ -0,0 +1,91 @@
+use cosmwasm_std::{attr, Addr, Event, Response};
+
+use crate::{
+    context::Context,
+    ensure::ensure_authorized_code_id,
+    error::ContractError,
+    execute::client::create::{build_setup_msg, insert_contract},
+    msg::RegistrationParams,
+    state::{
+        append_group, ensure_allowed_by_acl, ensure_partition_exists, query_contract_info,
+        resolve_partition_id, ARCHIVED_ADDR_2_ID, CONTRACT_USES_LIFECYCLE_HOOKS,
+    },
+};
+
+/// Add a contract that was instantiated outside of the table, allocating it a
+/// new contract ID and inserting it into the built-in indices.
+pub fn on_execute(
+    ctx: Context,
+    params: RegistrationParams,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, env } = ctx;
+    let action = "register";
+
+    let contract_addr = deps.api.addr_validate(params.contract.as_str())?;
+
+    // A contract may register itself. Otherwise, only allow sender if auth'd by
+    // owner address or ACL.
+    if contract_addr != info.sender {
+        ensure_allowed_by_acl(&deps, &info.sender, "/table/register")?;
+    }
+
+    // Archived contracts keep their ID and stored values, so they must be
+    // brought back with Restore rather than registered anew.
+    if ARCHIVED_ADDR_2_ID.has(deps.storage, &contract_addr) {
+        return Err(ContractError::ValidationError {
+            reason: format!(
+                "contract {} is archived; use Restore instead",
+                contract_addr
+            ),
+        });
+    }
+
+    // Get the contract's real code ID and admin from the chain
+    let contract_info = query_contract_info(deps.as_ref(), &contract_addr)?;
+
+    ensure_authorized_code_id(deps.storage, contract_info.code_id)?;
+
+    let p = resolve_partition_id(deps.storage, params.partition)?;
+
+    ensure_partition_exists(deps.storage, p)?;
+
+    let initiator = &info.sender;
+    let contract_id = insert_contract(
+        deps.storage,
+        &env,
+        &contract_addr,
+        contract_info.code_id,
+        initiator,
+        p,
+        contract_info.admin.map(Addr::unchecked),
+    )?;
+
+    let use_lifecycle_hooks = params.use_lifecycle_hooks.unwrap_or_default();
+
+    CONTRACT_USES_LIFECYCLE_HOOKS.save(deps.storage, contract_id, &use_lifecycle_hooks)?;
+
+    if let Some(group_ids) = params.groups {
+        for group_id in group_ids.iter() {
+            append_group(deps.storage, *group_id, contract_id)?;
+        }
+    }
+
+    let mut resp = Response::new()
+        .add_attributes(vec![attr("action", action)])
+        .add_event(
+            Event::new("post_register")
+                .add_attribute("contract_address", contract_addr.to_string())
+                .add_attribute("contract_id", contract_id.to_string()),
+        );
+
+    if use_lifecycle_hooks {
+        resp = resp.add_message(build_setup_msg(
+            &env,
+            &contract_addr,
+            initiator,
+            contract_id,
+        )?);
+    }
+
+    Ok(resp)
+}
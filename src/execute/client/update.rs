@@ -1,21 +1,37 @@
 use crate::{
     context::Context,
     error::ContractError,
-    models::DynamicContractMetadata,
-    msg::{IndexType, KeyValue, Relationship, RelationshipUpdates, TagUpdates, UpdateParams},
+    models::{DynamicContractMetadata, IndexAggregate},
+    msg::{
+        GeoPoint, IndexFacet, IndexMetadata, IndexSetUpdates, IndexType, IndexValue, KeyValue,
+        Relationship, RelationshipUpdates, TagUpdates, UniquenessScope, UpdateParams,
+    },
     state::{
         decrement_tag_count, ensure_allowed_by_acl, ensure_contract_not_suspended,
-        increment_tag_count, load_contract_id, ContractID, CustomIndexMap, PartitionID,
-        CONFIG_STR_CASE_SENSITIVE, CONFIG_STR_MAX_LEN, CONTRACT_DYN_METADATA, CONTRACT_INDEX_TYPES,
-        CONTRACT_METADATA, CONTRACT_TAGS, INDEX_METADATA, IX_REV, IX_TAG, IX_UPDATED_AT,
-        IX_UPDATED_BY, NOT_UNIQUE, REL_ADDR_2_ID, REL_ID_2_ADDR, UNIQUE, VALUES_BINARY,
-        VALUES_BOOL, VALUES_I32, VALUES_STRING, VALUES_TIME, VALUES_U128, VALUES_U16, VALUES_U32,
-        VALUES_U64, VALUES_U8, X,
+        increment_tag_count, load_contract_group_ids, load_contract_id, load_index_set_values,
+        load_index_value, load_string_index_settings, set_contract_expiry, CompoundIndexMap,
+        ContractID, CustomIndexMap, GroupID, PartitionID, TextIndexMap, COMPOUND_INDEX_METADATA,
+        CONFIG_STR_MAX_LEN, CONTRACT_DYN_METADATA, CONTRACT_INDEX_TYPES, CONTRACT_METADATA,
+        CONTRACT_TAGS, GROUP_INDEX_AGGREGATES, GROUP_INDEX_VALUES, INDEX_COMPOUNDS,
+        INDEX_COUNTED_VALUES, INDEX_FACET_COUNTS, INDEX_METADATA, INDEX_UNIQUE_VALUES, IX_REV,
+        IX_TAG, IX_UPDATED_AT, IX_UPDATED_BY, NOT_UNIQUE, PARTITION_INDEX_AGGREGATES,
+        REL_ADDR_2_ID, REL_ID_2_ADDR, REQUIRED_INDICES, UNIQUE, VALUES_ADDR, VALUES_BINARY,
+        VALUES_BOOL, VALUES_COMPOUND, VALUES_DECIMAL, VALUES_DECIMAL256, VALUES_GEO, VALUES_I128,
+        VALUES_I32, VALUES_I64, VALUES_SET, VALUES_STRING, VALUES_TIME, VALUES_U128, VALUES_U16,
+        VALUES_U32, VALUES_U64, VALUES_U8, X,
+    },
+    util::{
+        build_index_storage_key, build_text_index_storage_key, encode_ordered, geohash, glob_match,
+        is_numeric_index_type, normalize_index_str, pad, parse, parse_index_value, tokenize_text,
+        trim_padding, validate_geo_point, GEOHASH_PRECISION,
     },
-    util::{build_index_storage_key, pad, trim_padding},
 };
-use cosmwasm_std::{attr, Addr, Binary, Env, Order, Response, Storage, Timestamp, Uint128, Uint64};
-use cw_storage_plus::Map;
+use cosmwasm_std::{
+    attr, Addr, Api, Binary, Decimal, Decimal256, Env, Order, Response, StdResult, Storage,
+    Timestamp, Uint128, Uint256, Uint64,
+};
+use cw_storage_plus::{Map, PrimaryKey};
+use std::collections::BTreeSet;
 
 pub fn on_execute(
     ctx: Context,
@@ -47,11 +63,11 @@ pub fn on_execute(
     if let Some(index_updates) = params.values {
         upsert_metadata(deps.storage, &env, partition, &initiator, contract_id)?;
         update_indices(
+            deps.api,
             deps.storage,
             partition,
             contract_id,
             index_updates,
-            max_str_len,
         )?;
     }
 
@@ -71,6 +87,11 @@ pub fn on_execute(
         update_relationships(deps.storage, contract_id, rel_updates, max_str_len)?;
     }
 
+    // Replace expiry settings
+    if let Some(expiry) = params.expiry {
+        set_contract_expiry(deps.storage, contract_id, Some(expiry))?;
+    }
+
     Ok(Response::new().add_attributes(vec![attr("action", action)]))
 }
 
@@ -105,13 +126,26 @@ fn upsert_metadata(
         },
     )?;
 
-    if let Some(prev_meta) = maybe_prev_meta {
-        IX_REV.remove(storage, (partition, prev_meta.rev.into(), contract_id));
-        IX_UPDATED_AT.remove(
-            storage,
-            (partition, prev_meta.updated_at.nanos(), contract_id),
-        );
-    }
+    // Until the first update, the contract is indexed by its creation-time
+    // values, which must be replaced.
+    let (prev_rev, prev_updated_at, prev_updated_by) = match maybe_prev_meta {
+        Some(prev_meta) => (
+            prev_meta.rev.u64(),
+            prev_meta.updated_at,
+            prev_meta.updated_by,
+        ),
+        None => {
+            let meta = CONTRACT_METADATA.load(storage, contract_id)?;
+            (1, meta.created_at, meta.created_by)
+        },
+    };
+
+    IX_REV.remove(storage, (partition, prev_rev, contract_id));
+    IX_UPDATED_AT.remove(storage, (partition, prev_updated_at.nanos(), contract_id));
+    IX_UPDATED_BY.remove(
+        storage,
+        (partition, prev_updated_by.to_string(), contract_id),
+    );
 
     IX_REV.save(storage, (partition, meta.rev.into(), contract_id), &X)?;
     IX_UPDATED_AT.save(
@@ -124,7 +158,7 @@ fn upsert_metadata(
     Ok(())
 }
 
-fn update_tags(
+pub fn update_tags(
     storage: &mut dyn Storage,
     partition: PartitionID,
     contract_id: ContractID,
@@ -195,7 +229,7 @@ fn ensure_uniqueness(
     Ok(())
 }
 
-fn update_relationships(
+pub fn update_relationships(
     storage: &mut dyn Storage,
     contract_id: ContractID,
     updates: RelationshipUpdates,
@@ -278,27 +312,57 @@ fn remove_relationship(
     Ok(())
 }
 
-fn update_indices(
+pub fn update_indices(
+    api: &dyn Api,
     storage: &mut dyn Storage,
     partition: PartitionID,
     contract_id: ContractID,
     index_updates: Vec<KeyValue>,
-    max_str_len: usize,
 ) -> Result<(), ContractError> {
+    ensure_valid_index_updates(api, storage, &index_updates)?;
+
+    // Normalize string values to the form in which they're stored
+    let index_updates = index_updates
+        .into_iter()
+        .map(|kv| match kv {
+            KeyValue::String(key, Some(value)) => {
+                let value = normalize_string_index_value(storage, &key, &value)?;
+                Ok(KeyValue::String(key, Some(value)))
+            },
+            KeyValue::Set(key, updates) => {
+                let updates = IndexSetUpdates {
+                    remove: normalize_index_values(storage, &key, updates.remove)?,
+                    add: normalize_index_values(storage, &key, updates.add)?,
+                };
+                Ok(KeyValue::Set(key, updates))
+            },
+            kv => Ok(kv),
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
     // Update each index for the given KeyValue. If the given value is None, use
     // this as a signal to remove the existing entry, if any, from the index.
-    let is_case_sensitive = CONFIG_STR_CASE_SENSITIVE.load(storage)?;
     for value in index_updates.iter() {
+        let (index_name, maybe_index_type, maybe_value) = split_key_value(value);
+        update_unique_index_value(storage, partition, contract_id, index_name, maybe_value)?;
+
+        // Note the old value of numeric, faceted and text indices, to update
+        // their aggregates, facet counts and words
+        let is_derived = maybe_index_type.is_some()
+            && (maybe_index_type
+                .as_ref()
+                .map_or(false, is_numeric_index_type)
+                || has_derived_index_data(storage, index_name)?);
+        let maybe_old_value = if is_derived {
+            load_scalar_index_value(storage, contract_id, index_name)?
+        } else {
+            None
+        };
+
         match value {
-            KeyValue::String(key, value) => update_string_index(
-                storage,
-                partition,
-                contract_id,
-                key,
-                value,
-                max_str_len,
-                is_case_sensitive,
-            )?,
+            KeyValue::String(key, value) => {
+                update_string_index(storage, partition, contract_id, key, value)?
+            },
             KeyValue::Bool(key, value) => {
                 update_bool_index(storage, partition, contract_id, key, value)?
             },
@@ -326,11 +390,1186 @@ fn update_indices(
             KeyValue::Binary(key, value) => {
                 update_binary_index(storage, partition, contract_id, key, value)?
             },
+            KeyValue::Int64(key, value) => {
+                update_i64_index(storage, partition, contract_id, key, value)?
+            },
+            KeyValue::Int128(key, value) => {
+                update_i128_index(storage, partition, contract_id, key, value)?
+            },
+            KeyValue::Decimal(key, value) => {
+                update_decimal_index(storage, partition, contract_id, key, value)?
+            },
+            KeyValue::Decimal256(key, value) => {
+                update_decimal256_index(storage, partition, contract_id, key, value)?
+            },
+            KeyValue::Addr(key, value) => {
+                update_addr_index(storage, partition, contract_id, key, value)?
+            },
+            KeyValue::Geo(key, value) => {
+                update_geo_index(storage, partition, contract_id, key, value)?
+            },
+            KeyValue::Set(key, updates) => {
+                update_index_set(storage, partition, contract_id, key, updates)?
+            },
+        }
+
+        if is_derived {
+            let maybe_new_value = load_scalar_index_value(storage, contract_id, index_name)?;
+            if maybe_new_value != maybe_old_value {
+                if let Some(old_value) = &maybe_old_value {
+                    update_derived_index_data(
+                        storage,
+                        partition,
+                        contract_id,
+                        index_name,
+                        old_value,
+                        false,
+                    )?;
+                }
+                if let Some(new_value) = &maybe_new_value {
+                    update_derived_index_data(
+                        storage,
+                        partition,
+                        contract_id,
+                        index_name,
+                        new_value,
+                        true,
+                    )?;
+                }
+            }
+        }
+    }
+
+    // Rebuild entries in compound indices that include any updated index
+    let mut compound_index_names: BTreeSet<String> = BTreeSet::new();
+    for value in index_updates.iter() {
+        for result in INDEX_COMPOUNDS.prefix(split_key_value(value).0).keys(
+            storage,
+            None,
+            None,
+            Order::Ascending,
+        ) {
+            compound_index_names.insert(result?);
+        }
+    }
+    for compound_index_name in compound_index_names.iter() {
+        update_compound_index(storage, partition, contract_id, compound_index_name)?;
+    }
+
+    Ok(())
+}
+
+/// Split a KeyValue into its index name, type and value, if any. Set updates
+/// carry their own typed elements, so they have neither a type nor a value.
+fn split_key_value(value: &KeyValue) -> (&String, Option<IndexType>, Option<IndexValue>) {
+    match value {
+        KeyValue::String(key, v) => (
+            key,
+            Some(IndexType::String),
+            v.clone().map(IndexValue::String),
+        ),
+        KeyValue::Bool(key, v) => (key, Some(IndexType::Bool), v.map(IndexValue::Bool)),
+        KeyValue::Timestamp(key, v) => (
+            key,
+            Some(IndexType::Timestamp),
+            v.map(IndexValue::Timestamp),
+        ),
+        KeyValue::Int32(key, v) => (key, Some(IndexType::Int32), v.map(IndexValue::Int32)),
+        KeyValue::Uint8(key, v) => (key, Some(IndexType::Uint8), v.map(IndexValue::Uint8)),
+        KeyValue::Uint16(key, v) => (key, Some(IndexType::Uint16), v.map(IndexValue::Uint16)),
+        KeyValue::Uint32(key, v) => (key, Some(IndexType::Uint32), v.map(IndexValue::Uint32)),
+        KeyValue::Uint64(key, v) => (key, Some(IndexType::Uint64), v.map(IndexValue::Uint64)),
+        KeyValue::Uint128(key, v) => (key, Some(IndexType::Uint128), v.map(IndexValue::Uint128)),
+        KeyValue::Binary(key, v) => (
+            key,
+            Some(IndexType::Binary),
+            v.clone().map(IndexValue::Binary),
+        ),
+        KeyValue::Int64(key, v) => (key, Some(IndexType::Int64), v.map(IndexValue::Int64)),
+        KeyValue::Int128(key, v) => (key, Some(IndexType::Int128), v.map(IndexValue::Int128)),
+        KeyValue::Decimal(key, v) => (key, Some(IndexType::Decimal), v.map(IndexValue::Decimal)),
+        KeyValue::Decimal256(key, v) => (
+            key,
+            Some(IndexType::Decimal256),
+            v.map(IndexValue::Decimal256),
+        ),
+        KeyValue::Addr(key, v) => (key, Some(IndexType::Addr), v.clone().map(IndexValue::Addr)),
+        KeyValue::Geo(key, v) => (key, Some(IndexType::Geo), v.clone().map(IndexValue::Geo)),
+        KeyValue::Set(key, _) => (key, None, None),
+    }
+}
+
+/// Get the type of index to which a value belongs.
+fn index_value_type(value: &IndexValue) -> IndexType {
+    match value {
+        IndexValue::String(_) => IndexType::String,
+        IndexValue::Bool(_) => IndexType::Bool,
+        IndexValue::Timestamp(_) => IndexType::Timestamp,
+        IndexValue::Int32(_) => IndexType::Int32,
+        IndexValue::Uint8(_) => IndexType::Uint8,
+        IndexValue::Uint16(_) => IndexType::Uint16,
+        IndexValue::Uint32(_) => IndexType::Uint32,
+        IndexValue::Uint64(_) => IndexType::Uint64,
+        IndexValue::Uint128(_) => IndexType::Uint128,
+        IndexValue::Binary(_) => IndexType::Binary,
+        IndexValue::Int64(_) => IndexType::Int64,
+        IndexValue::Int128(_) => IndexType::Int128,
+        IndexValue::Decimal(_) => IndexType::Decimal,
+        IndexValue::Decimal256(_) => IndexType::Decimal256,
+        IndexValue::Addr(_) => IndexType::Addr,
+        IndexValue::Geo(_) => IndexType::Geo,
+    }
+}
+
+/// Ensure that each KeyValue targets an existing custom index of the same
+/// type, and that each new value satisfies the index's constraints.
+pub fn ensure_valid_index_updates(
+    api: &dyn Api,
+    storage: &dyn Storage,
+    index_updates: &[KeyValue],
+) -> Result<(), ContractError> {
+    for kv in index_updates.iter() {
+        let (index_name, maybe_index_type, maybe_value) = split_key_value(kv);
+        let meta = INDEX_METADATA
+            .may_load(storage, index_name.clone())?
+            .ok_or_else(|| ContractError::UnknownIndex {
+                index: index_name.clone(),
+            })?;
+        if meta.deletion.is_some() {
+            return Err(ContractError::IndexDeleting {
+                index: index_name.clone(),
+            });
+        }
+
+        // Set updates apply only to set-valued indices, and vice versa
+        let is_set_update = maybe_index_type.is_none();
+        if meta.multi.unwrap_or(false) != is_set_update {
+            return Err(ContractError::ValidationError {
+                reason: if is_set_update {
+                    format!("index {} is not set-valued", index_name)
+                } else {
+                    format!("index {} is set-valued", index_name)
+                },
+            });
+        }
+
+        let (removed_values, new_values) = match kv {
+            KeyValue::Set(_, updates) => (
+                updates.remove.clone().unwrap_or_default(),
+                updates.add.clone().unwrap_or_default(),
+            ),
+            _ => (vec![], maybe_value.into_iter().collect()),
+        };
+
+        let value_types = removed_values
+            .iter()
+            .chain(new_values.iter())
+            .map(index_value_type);
+        for index_type in maybe_index_type.into_iter().chain(value_types) {
+            if meta.index_type != index_type {
+                return Err(ContractError::IndexTypeMismatch {
+                    index: index_name.clone(),
+                    expected: meta.index_type,
+                    actual: index_type,
+                });
+            }
+        }
+
+        for value in new_values.iter() {
+            match value {
+                IndexValue::Addr(addr) => {
+                    api.addr_validate(addr.as_str())?;
+                },
+                IndexValue::String(s) => {
+                    normalize_string_index_value(storage, index_name, s)?;
+                },
+                IndexValue::Geo(point) => {
+                    validate_geo_point(point)?;
+                },
+                _ => {},
+            }
+            ensure_index_value_allowed(&meta, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Normalize a value of the given string index to its stored form.
+fn normalize_string_index_value(
+    storage: &dyn Storage,
+    index_name: &String,
+    value: &str,
+) -> Result<String, ContractError> {
+    let settings = load_string_index_settings(storage, index_name)?;
+    normalize_index_str(index_name, &settings, value, true)
+}
+
+/// Normalize the string elements of a set update to their stored form.
+fn normalize_index_values(
+    storage: &dyn Storage,
+    index_name: &String,
+    maybe_values: Option<Vec<IndexValue>>,
+) -> Result<Option<Vec<IndexValue>>, ContractError> {
+    maybe_values
+        .map(|values| {
+            values
+                .into_iter()
+                .map(|value| match value {
+                    IndexValue::String(s) => Ok(IndexValue::String(normalize_string_index_value(
+                        storage, index_name, &s,
+                    )?)),
+                    value => Ok(value),
+                })
+                .collect::<Result<Vec<_>, ContractError>>()
+        })
+        .transpose()
+}
+
+/// Ensure that the given values include one for each required index.
+pub fn ensure_required_index_values(
+    storage: &dyn Storage,
+    values: &[KeyValue],
+) -> Result<(), ContractError> {
+    for result in REQUIRED_INDICES.keys(storage, None, None, Order::Ascending) {
+        let index_name = result?;
+        if !values.iter().any(|kv| {
+            let (name, _, maybe_value) = split_key_value(kv);
+            let is_set_nonempty = match kv {
+                KeyValue::Set(_, updates) => updates.add.iter().flatten().next().is_some(),
+                _ => false,
+            };
+            *name == index_name && (maybe_value.is_some() || is_set_nonempty)
+        }) {
+            return Err(ContractError::IndexConstraintViolation {
+                index: index_name,
+                reason: "value is required".to_owned(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn ensure_index_value_allowed(
+    meta: &IndexMetadata,
+    value: &IndexValue,
+) -> Result<(), ContractError> {
+    let constraints = match &meta.constraints {
+        Some(constraints) => constraints,
+        None => return Ok(()),
+    };
+
+    let violation = |reason: String| ContractError::IndexConstraintViolation {
+        index: meta.name.clone(),
+        reason,
+    };
+
+    // Bound the length of strings and binaries, or the value of anything else
+    let maybe_len = match value {
+        IndexValue::String(s) => Some(s.chars().count()),
+        IndexValue::Binary(b) => Some(b.len()),
+        _ => None,
+    };
+
+    if let Some(len) = maybe_len {
+        if let Some(min) = &constraints.min {
+            if len < parse::<u32>(min.clone())? as usize {
+                return Err(violation(format!("length is less than {}", min)));
+            }
+        }
+        if let Some(max) = &constraints.max {
+            if len > parse::<u32>(max.clone())? as usize {
+                return Err(violation(format!("length is greater than {}", max)));
+            }
+        }
+    } else {
+        let encoded_value = encode_ordered(value);
+        if let Some(min) = &constraints.min {
+            let min_value = parse_index_value(&meta.index_type, min.clone())?;
+            if encoded_value < encode_ordered(&min_value) {
+                return Err(violation(format!("value is less than {}", min)));
+            }
+        }
+        if let Some(max) = &constraints.max {
+            let max_value = parse_index_value(&meta.index_type, max.clone())?;
+            if encoded_value > encode_ordered(&max_value) {
+                return Err(violation(format!("value is greater than {}", max)));
+            }
+        }
+    }
+
+    if let (Some(pattern), IndexValue::String(s)) = (&constraints.pattern, value) {
+        if !glob_match(pattern, s) {
+            return Err(violation(format!(
+                "value does not match pattern {}",
+                pattern
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Move a contract's claim in a unique index from its stored value to the new
+/// one, failing if another contract has already claimed the new value.
+fn update_unique_index_value(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: Option<IndexValue>,
+) -> Result<(), ContractError> {
+    let meta = INDEX_METADATA.load(storage, index_name.clone())?;
+    let scope = match &meta.unique {
+        Some(scope) => scope,
+        None => return Ok(()),
+    };
+
+    if let Some(index_type) = CONTRACT_INDEX_TYPES.may_load(storage, (contract_id, index_name))? {
+        let old_value = load_index_value(storage, contract_id, index_name, &index_type)?;
+        set_unique_index_value(
+            storage,
+            scope,
+            partition,
+            contract_id,
+            index_name,
+            &old_value,
+            false,
+        )?;
+    }
+
+    if let Some(value) = maybe_value {
+        set_unique_index_value(
+            storage,
+            scope,
+            partition,
+            contract_id,
+            index_name,
+            &value,
+            true,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Claim or release a value of a unique index for the given contract.
+fn set_unique_index_value(
+    storage: &mut dyn Storage,
+    scope: &UniquenessScope,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let mut key: Vec<u8> = match scope {
+        UniquenessScope::Partition => partition.to_be_bytes().to_vec(),
+        UniquenessScope::Table => vec![],
+    };
+    key.extend(encode_ordered(value));
+
+    let maybe_owner_id = INDEX_UNIQUE_VALUES.may_load(storage, (index_name, key.clone()))?;
+
+    if is_insert {
+        if let Some(owner_id) = maybe_owner_id {
+            if owner_id != contract_id {
+                return Err(ContractError::UniqueIndexConflict {
+                    index: index_name.clone(),
+                    contract_id: owner_id,
+                });
+            }
+        }
+        INDEX_UNIQUE_VALUES.save(storage, (index_name, key), &contract_id)?;
+    } else if maybe_owner_id == Some(contract_id) {
+        INDEX_UNIQUE_VALUES.remove(storage, (index_name, key));
+    }
+
+    Ok(())
+}
+
+/// Remove a contract's stored value, or set of values, for the given custom
+/// index.
+pub fn remove_index_value(
+    storage: &mut dyn Storage,
+    contract_id: ContractID,
+    index_name: &String,
+    index_type: &IndexType,
+) -> Result<(), ContractError> {
+    let key = (contract_id, index_name);
+    CONTRACT_INDEX_TYPES.remove(storage, key);
+    let element_keys: Vec<Vec<u8>> = VALUES_SET
+        .prefix(key)
+        .keys_raw(storage, None, None, Order::Ascending)
+        .collect();
+    for element_key in element_keys.iter() {
+        VALUES_SET.remove(storage, (contract_id, index_name, element_key.as_slice()));
+    }
+    match index_type {
+        IndexType::String => VALUES_STRING.remove(storage, key),
+        IndexType::Bool => VALUES_BOOL.remove(storage, key),
+        IndexType::Timestamp => VALUES_TIME.remove(storage, key),
+        IndexType::Int32 => VALUES_I32.remove(storage, key),
+        IndexType::Uint8 => VALUES_U8.remove(storage, key),
+        IndexType::Uint16 => VALUES_U16.remove(storage, key),
+        IndexType::Uint32 => VALUES_U32.remove(storage, key),
+        IndexType::Uint64 => VALUES_U64.remove(storage, key),
+        IndexType::Uint128 => VALUES_U128.remove(storage, key),
+        IndexType::Binary => VALUES_BINARY.remove(storage, key),
+        IndexType::Int64 => VALUES_I64.remove(storage, key),
+        IndexType::Int128 => VALUES_I128.remove(storage, key),
+        IndexType::Decimal => VALUES_DECIMAL.remove(storage, key),
+        IndexType::Decimal256 => VALUES_DECIMAL256.remove(storage, key),
+        IndexType::Addr => VALUES_ADDR.remove(storage, key),
+        IndexType::Geo => VALUES_GEO.remove(storage, key),
+    }
+    Ok(())
+}
+
+/// Re-key a contract's entry in a compound index from the current values of
+/// its components. Contracts without a value for every component are left out
+/// of the index. Returns true if a new entry was inserted.
+pub fn update_compound_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+) -> Result<bool, ContractError> {
+    let meta = COMPOUND_INDEX_METADATA.load(storage, index_name.clone())?;
+
+    let mut maybe_new_val: Option<Vec<u8>> = Some(vec![]);
+    for component in meta.components.iter() {
+        if let Some(index_type) =
+            CONTRACT_INDEX_TYPES.may_load(storage, (contract_id, component))?
+        {
+            if let Some(bytes) = maybe_new_val.as_mut() {
+                let value = load_index_value(storage, contract_id, component, &index_type)?;
+                bytes.extend(encode_ordered(&value));
+            }
+        } else {
+            maybe_new_val = None;
+            break;
+        }
+    }
+
+    let index_storage_key = build_index_storage_key(index_name);
+    let index: CompoundIndexMap = Map::new(&index_storage_key);
+
+    // Nothing to do if the entry is already up to date
+    let maybe_old_val = VALUES_COMPOUND.may_load(storage, (contract_id, index_name))?;
+    if maybe_old_val.as_ref().map(|v| v.as_slice()) == maybe_new_val.as_deref() {
+        match &maybe_new_val {
+            None => return Ok(false),
+            Some(new_val) => {
+                if index.has(
+                    storage,
+                    (partition, build_compound_key(new_val, contract_id)),
+                ) {
+                    return Ok(false);
+                }
+            },
+        }
+    }
+
+    if let Some(old_val) = maybe_old_val {
+        let index_key = (partition, build_compound_key(&old_val, contract_id));
+        if index.has(storage, index_key.clone()) {
+            index.remove(storage, index_key);
+            increment_compound_index_size(storage, index_name, false)?;
+        }
+    }
+
+    if let Some(new_val) = maybe_new_val {
+        index.save(
+            storage,
+            (partition, build_compound_key(&new_val, contract_id)),
+            &X,
+        )?;
+        VALUES_COMPOUND.save(storage, (contract_id, index_name), &Binary::from(new_val))?;
+        increment_compound_index_size(storage, index_name, true)?;
+        Ok(true)
+    } else {
+        VALUES_COMPOUND.remove(storage, (contract_id, index_name));
+        Ok(false)
+    }
+}
+
+/// Append the contract ID to an encoded compound value to form the key of its
+/// entry in a compound index.
+pub fn build_compound_key(
+    value: &[u8],
+    contract_id: ContractID,
+) -> Vec<u8> {
+    let mut key = value.to_vec();
+    key.extend(contract_id.to_be_bytes());
+    key
+}
+
+/// Insert or remove all of a contract's custom index entries in the given
+/// partition, using the values stored for each index. Stored values are kept,
+/// so that removed entries can be inserted again later.
+pub fn set_custom_index_entries(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let entries: Vec<(String, IndexType)> = CONTRACT_INDEX_TYPES
+        .prefix(contract_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (index_name, index_type) in entries.iter() {
+        match INDEX_METADATA.may_load(storage, index_name.clone())? {
+            // Drop values left behind by deleted indices
+            None => {
+                remove_index_value(storage, contract_id, index_name, index_type)?;
+                continue;
+            },
+            // Don't add entries to indices being deleted
+            Some(meta) if meta.deletion.is_some() && is_insert => continue,
+            _ => {},
+        }
+        set_custom_index_entry(
+            storage,
+            partition,
+            contract_id,
+            index_name,
+            index_type,
+            is_insert,
+        )?;
+    }
+
+    let compound_entries: Vec<(String, Binary)> = VALUES_COMPOUND
+        .prefix(contract_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (index_name, value) in compound_entries.iter() {
+        match COMPOUND_INDEX_METADATA.may_load(storage, index_name.clone())? {
+            // Drop values left behind by deleted compound indices
+            None => {
+                VALUES_COMPOUND.remove(storage, (contract_id, index_name));
+                continue;
+            },
+            // Don't add entries to compound indices being deleted
+            Some(meta) if meta.deletion.is_some() && is_insert => continue,
+            _ => {},
+        }
+        let index_storage_key = build_index_storage_key(index_name);
+        let index: CompoundIndexMap = Map::new(&index_storage_key);
+        let index_key = (partition, build_compound_key(value, contract_id));
+        if set_index_entry(storage, index, index_key, is_insert)? {
+            increment_compound_index_size(storage, index_name, is_insert)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Insert or remove a contract's entry in a single custom index, using its
+/// stored value, or its entries for each stored element of a set-valued index.
+/// Returns true if the index changed.
+pub fn set_custom_index_entry(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    index_type: &IndexType,
+    is_insert: bool,
+) -> Result<bool, ContractError> {
+    let meta = INDEX_METADATA.load(storage, index_name.clone())?;
+
+    if meta.multi.unwrap_or(false) {
+        let mut is_changed = false;
+        for value in load_index_set_values(storage, contract_id, index_name)?.iter() {
+            is_changed |= set_index_value_entry(
+                storage,
+                partition,
+                contract_id,
+                index_name,
+                value,
+                is_insert,
+            )?;
+        }
+        return Ok(is_changed);
+    }
+
+    let value = load_index_value(storage, contract_id, index_name, index_type)?;
+
+    if let Some(scope) = meta.unique {
+        set_unique_index_value(
+            storage,
+            &scope,
+            partition,
+            contract_id,
+            index_name,
+            &value,
+            is_insert,
+        )?;
+    }
+
+    set_index_value_entry(
+        storage,
+        partition,
+        contract_id,
+        index_name,
+        &value,
+        is_insert,
+    )
+}
+
+/// Insert or remove the entry for a single value in a custom index, updating
+/// the index size. Returns true if the index changed.
+fn set_index_value_entry(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<bool, ContractError> {
+    let index_storage_key = build_index_storage_key(index_name);
+    let is_changed = match value {
+        IndexValue::String(value) => {
+            let index: CustomIndexMap<&String> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, value, contract_id), is_insert)?
+        },
+        IndexValue::Bool(value) => {
+            let index: CustomIndexMap<u8> = Map::new(&index_storage_key);
+            set_index_entry(
+                storage,
+                index,
+                (partition, (*value).into(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Timestamp(value) => {
+            let index: CustomIndexMap<u64> = Map::new(&index_storage_key);
+            set_index_entry(
+                storage,
+                index,
+                (partition, value.nanos(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Int32(value) => {
+            let index: CustomIndexMap<i32> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, *value, contract_id), is_insert)?
+        },
+        IndexValue::Uint8(value) => {
+            let index: CustomIndexMap<u8> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, *value, contract_id), is_insert)?
+        },
+        IndexValue::Uint16(value) => {
+            let index: CustomIndexMap<u16> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, *value, contract_id), is_insert)?
+        },
+        IndexValue::Uint32(value) => {
+            let index: CustomIndexMap<u32> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, *value, contract_id), is_insert)?
+        },
+        IndexValue::Uint64(value) => {
+            let index: CustomIndexMap<u64> = Map::new(&index_storage_key);
+            set_index_entry(
+                storage,
+                index,
+                (partition, value.u64(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Uint128(value) => {
+            let index: CustomIndexMap<u128> = Map::new(&index_storage_key);
+            set_index_entry(
+                storage,
+                index,
+                (partition, value.u128(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Binary(value) => {
+            let index: CustomIndexMap<&[u8]> = Map::new(&index_storage_key);
+            set_index_entry(
+                storage,
+                index,
+                (partition, value.as_slice(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Int64(value) => {
+            let index: CustomIndexMap<i64> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, *value, contract_id), is_insert)?
+        },
+        IndexValue::Int128(value) => {
+            let index: CustomIndexMap<i128> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, *value, contract_id), is_insert)?
+        },
+        IndexValue::Decimal(value) => {
+            let index: CustomIndexMap<u128> = Map::new(&index_storage_key);
+            set_index_entry(
+                storage,
+                index,
+                (partition, value.atomics().u128(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Decimal256(value) => {
+            let index: CustomIndexMap<&[u8]> = Map::new(&index_storage_key);
+            let bytes = value.atomics().to_be_bytes();
+            set_index_entry(
+                storage,
+                index,
+                (partition, bytes.as_slice(), contract_id),
+                is_insert,
+            )?
+        },
+        IndexValue::Addr(value) => {
+            let index: CustomIndexMap<&Addr> = Map::new(&index_storage_key);
+            set_index_entry(storage, index, (partition, value, contract_id), is_insert)?
+        },
+        IndexValue::Geo(value) => {
+            let index: CustomIndexMap<&str> = Map::new(&index_storage_key);
+            let hash = geohash(value, GEOHASH_PRECISION);
+            set_index_entry(
+                storage,
+                index,
+                (partition, hash.as_str(), contract_id),
+                is_insert,
+            )?
+        },
+    };
+
+    if is_changed {
+        increment_index_size(storage, index_name, is_insert)?;
+    }
+
+    // Derived data is also added for entries that already exist, which
+    // backfills values indexed before it was maintained, e.g. on reindex.
+    if is_changed || is_insert {
+        update_derived_index_data(
+            storage,
+            partition,
+            contract_id,
+            index_name,
+            value,
+            is_insert,
+        )?;
+    }
+
+    Ok(is_changed)
+}
+
+/// Load a contract's stored value for a single-valued custom index, if any.
+fn load_scalar_index_value(
+    storage: &dyn Storage,
+    contract_id: ContractID,
+    index_name: &String,
+) -> Result<Option<IndexValue>, ContractError> {
+    match CONTRACT_INDEX_TYPES.may_load(storage, (contract_id, index_name))? {
+        Some(index_type) => Ok(Some(load_index_value(
+            storage,
+            contract_id,
+            index_name,
+            &index_type,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+/// Split a numeric value into its sign and magnitude, for use in aggregates.
+/// Decimals are represented by their atomics. Returns None for other values.
+fn split_numeric_value(value: &IndexValue) -> Option<(bool, Uint256)> {
+    match value {
+        IndexValue::Int32(v) => Some((*v < 0, Uint256::from(v.unsigned_abs()))),
+        IndexValue::Uint8(v) => Some((false, Uint256::from(*v))),
+        IndexValue::Uint16(v) => Some((false, Uint256::from(*v))),
+        IndexValue::Uint32(v) => Some((false, Uint256::from(*v))),
+        IndexValue::Uint64(v) => Some((false, Uint256::from(*v))),
+        IndexValue::Uint128(v) => Some((false, Uint256::from(*v))),
+        IndexValue::Int64(v) => Some((*v < 0, Uint256::from(v.unsigned_abs()))),
+        IndexValue::Int128(v) => Some((*v < 0, Uint256::from(v.unsigned_abs()))),
+        IndexValue::Decimal(v) => Some((false, Uint256::from(v.atomics()))),
+        IndexValue::Decimal256(v) => Some((false, v.atomics())),
+        _ => None,
+    }
+}
+
+/// Add or remove a contract's value of a custom index in the aggregates, facet
+/// counts and text index entries maintained for the index, if any. Values are
+/// only counted once, so this can be repeated, and values that were never
+/// counted, such as those indexed before aggregates existed, aren't removed.
+fn update_derived_index_data(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let maybe_meta = INDEX_METADATA.may_load(storage, index_name.clone())?;
+    let has_facets = maybe_meta
+        .as_ref()
+        .map_or(false, |meta| meta.facets.unwrap_or(false));
+
+    if split_numeric_value(value).is_some() || has_facets {
+        let value_key = encode_ordered(value);
+        let key = (index_name, contract_id, value_key.as_slice());
+        // Count in the partition in which the value was added, or not at all
+        let maybe_partition = match (INDEX_COUNTED_VALUES.may_load(storage, key)?, is_insert) {
+            (None, true) => {
+                INDEX_COUNTED_VALUES.save(storage, key, &partition)?;
+                Some(partition)
+            },
+            (Some(counted_partition), false) => {
+                INDEX_COUNTED_VALUES.remove(storage, key);
+                Some(counted_partition)
+            },
+            _ => None,
+        };
+        if let Some(partition) = maybe_partition {
+            update_partition_index_aggregate(storage, partition, index_name, value, is_insert)?;
+            if has_facets {
+                update_facet_count(storage, partition, index_name, value, is_insert)?;
+            }
+        }
+    }
+
+    // Group values and text index entries are already set idempotently
+    update_group_index_values(storage, contract_id, index_name, value, is_insert)?;
+
+    if let Some(meta) = maybe_meta {
+        if meta.text.unwrap_or(false) {
+            if let IndexValue::String(text) = value {
+                set_text_index_entries(
+                    storage,
+                    partition,
+                    contract_id,
+                    index_name,
+                    text,
+                    is_insert,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Return true if facet counts or text index entries are maintained for the
+/// given custom index.
+fn has_derived_index_data(
+    storage: &dyn Storage,
+    index_name: &String,
+) -> Result<bool, ContractError> {
+    Ok(INDEX_METADATA
+        .may_load(storage, index_name.clone())?
+        .map_or(false, |meta| {
+            meta.facets.unwrap_or(false) || meta.text.unwrap_or(false)
+        }))
+}
+
+/// Insert or remove an entry in a text index for each word of a contract's
+/// value of the underlying string index.
+fn set_text_index_entries(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    text: &String,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let storage_key = build_text_index_storage_key(index_name);
+    let index: TextIndexMap = Map::new(&storage_key);
+    for word in tokenize_text(&trim_padding(text)).iter() {
+        let key = (partition, word.as_str(), contract_id);
+        if is_insert {
+            index.save(storage, key, &X)?;
+        } else {
+            index.remove(storage, key);
         }
     }
     Ok(())
 }
 
+/// Increment or decrement the number of contracts in a partition with the
+/// given value of a custom index, removing the count once it reaches zero.
+fn update_facet_count(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let value_key = encode_ordered(value);
+    let key = (index_name, partition, value_key.as_slice());
+    let mut facet = INDEX_FACET_COUNTS
+        .may_load(storage, key)?
+        .unwrap_or_else(|| IndexFacet {
+            value: value.clone(),
+            count: 0,
+        });
+    if is_insert {
+        facet.count += 1;
+    } else {
+        facet.count = facet.count.saturating_sub(1);
+    }
+    if facet.count == 0 {
+        INDEX_FACET_COUNTS.remove(storage, key);
+    } else {
+        INDEX_FACET_COUNTS.save(storage, key, &facet)?;
+    }
+    Ok(())
+}
+
+/// Add or remove a value of a numeric custom index in the running aggregate of
+/// a partition. Values of other types are ignored.
+fn update_partition_index_aggregate(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let (is_negative, magnitude) = match split_numeric_value(value) {
+        Some(parts) => parts,
+        None => return Ok(()),
+    };
+    update_aggregate(
+        storage,
+        PARTITION_INDEX_AGGREGATES,
+        (index_name, partition),
+        is_negative,
+        magnitude,
+        is_insert,
+    )
+}
+
+/// Add or remove a contract's value of a numeric custom index in the values
+/// and aggregates of each of its groups. Values of other types are ignored.
+fn update_group_index_values(
+    storage: &mut dyn Storage,
+    contract_id: ContractID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    if split_numeric_value(value).is_none() {
+        return Ok(());
+    }
+    for group_id in load_contract_group_ids(storage, contract_id)? {
+        set_group_index_value(storage, group_id, contract_id, index_name, value, is_insert)?;
+    }
+    Ok(())
+}
+
+/// Add or remove a contract's value of a numeric custom index in the values
+/// and aggregates of a group, unless it's already been added or removed.
+fn set_group_index_value(
+    storage: &mut dyn Storage,
+    group_id: GroupID,
+    contract_id: ContractID,
+    index_name: &String,
+    value: &IndexValue,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let (is_negative, magnitude) = match split_numeric_value(value) {
+        Some(parts) => parts,
+        None => return Ok(()),
+    };
+    let value_key = build_compound_key(&encode_ordered(value), contract_id);
+    let key = (index_name, group_id, value_key.as_slice());
+    if GROUP_INDEX_VALUES.has(storage, key) == is_insert {
+        return Ok(());
+    }
+    if is_insert {
+        GROUP_INDEX_VALUES.save(storage, key, value)?;
+    } else {
+        GROUP_INDEX_VALUES.remove(storage, key);
+    }
+    update_aggregate(
+        storage,
+        GROUP_INDEX_AGGREGATES,
+        (index_name, group_id),
+        is_negative,
+        magnitude,
+        is_insert,
+    )
+}
+
+/// Add or remove a value in a running aggregate, removing the aggregate once
+/// it's empty. Removals saturate at zero rather than fail, so that a stale
+/// aggregate never blocks a write.
+fn update_aggregate<'a, K: PrimaryKey<'a> + Clone>(
+    storage: &mut dyn Storage,
+    aggregates: Map<'a, K, IndexAggregate>,
+    key: K,
+    is_negative: bool,
+    magnitude: Uint256,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    let mut aggregate = aggregates
+        .may_load(storage, key.clone())?
+        .unwrap_or_default();
+    let overflow = |_| ContractError::UnexpectedError {
+        reason: "Overflow updating index aggregate".to_owned(),
+    };
+    let sum = if is_negative {
+        &mut aggregate.sum_negative
+    } else {
+        &mut aggregate.sum_positive
+    };
+    if is_insert {
+        *sum = sum.checked_add(magnitude).map_err(overflow)?;
+        aggregate.count = aggregate
+            .count
+            .checked_add(Uint64::one())
+            .map_err(overflow)?;
+    } else {
+        *sum = sum.saturating_sub(magnitude);
+        aggregate.count = aggregate.count.saturating_sub(Uint64::one());
+    }
+    if aggregate.count.is_zero() {
+        aggregates.remove(storage, key);
+    } else {
+        aggregates.save(storage, key, &aggregate)?;
+    }
+    Ok(())
+}
+
+/// Add or remove a contract's current values of numeric custom indices in the
+/// values and aggregates of a group it's joining or leaving. Values are only
+/// added for contracts whose index entries are live, i.e. not archived.
+pub fn set_group_aggregate_entries(
+    storage: &mut dyn Storage,
+    group_id: GroupID,
+    contract_id: ContractID,
+    is_insert: bool,
+) -> Result<(), ContractError> {
+    if is_insert && !CONTRACT_METADATA.has(storage, contract_id) {
+        return Ok(());
+    }
+
+    let entries: Vec<(String, IndexType)> = CONTRACT_INDEX_TYPES
+        .prefix(contract_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (index_name, index_type) in entries.iter() {
+        if !is_numeric_index_type(index_type) {
+            continue;
+        }
+        let meta = match INDEX_METADATA.may_load(storage, index_name.clone())? {
+            Some(meta) => meta,
+            None => continue,
+        };
+        if is_insert && meta.deletion.is_some() {
+            continue;
+        }
+        let values = if meta.multi.unwrap_or(false) {
+            load_index_set_values(storage, contract_id, index_name)?
+        } else {
+            vec![load_index_value(
+                storage,
+                contract_id,
+                index_name,
+                index_type,
+            )?]
+        };
+        for value in values.iter() {
+            set_group_index_value(storage, group_id, contract_id, index_name, value, is_insert)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add and remove elements of a contract's set of values for a set-valued
+/// index, with an index entry for each element.
+fn update_index_set(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    updates: &IndexSetUpdates,
+) -> Result<(), ContractError> {
+    for value in updates.remove.iter().flatten() {
+        let element_key = encode_ordered(value);
+        let key = (contract_id, index_name, element_key.as_slice());
+        if VALUES_SET.has(storage, key) {
+            VALUES_SET.remove(storage, key);
+            set_index_value_entry(storage, partition, contract_id, index_name, value, false)?;
+        }
+    }
+
+    for value in updates.add.iter().flatten() {
+        let element_key = encode_ordered(value);
+        let key = (contract_id, index_name, element_key.as_slice());
+        if !VALUES_SET.has(storage, key) {
+            VALUES_SET.save(storage, key, value)?;
+            set_index_value_entry(storage, partition, contract_id, index_name, value, true)?;
+        }
+    }
+
+    // Only list the index among the contract's values while its set is nonempty
+    let is_empty = VALUES_SET
+        .prefix((contract_id, index_name))
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_none();
+    if is_empty {
+        CONTRACT_INDEX_TYPES.remove(storage, (contract_id, index_name));
+    } else if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, index_name)) {
+        let index_type = INDEX_METADATA.load(storage, index_name.clone())?.index_type;
+        CONTRACT_INDEX_TYPES.save(storage, (contract_id, index_name), &index_type)?;
+    }
+
+    Ok(())
+}
+
+/// Save or remove an index entry, returning true if the index changed.
+fn set_index_entry<'a, K: PrimaryKey<'a> + Clone>(
+    storage: &mut dyn Storage,
+    index: Map<'a, K, u8>,
+    key: K,
+    is_insert: bool,
+) -> Result<bool, ContractError> {
+    if index.has(storage, key.clone()) == is_insert {
+        return Ok(false);
+    }
+    if is_insert {
+        index.save(storage, key, &X)?;
+    } else {
+        index.remove(storage, key);
+    }
+    Ok(true)
+}
+
+/// Record the type of a contract's value for a single-valued custom index, or
+/// that it has none. The index size only changes when the contract gains or
+/// loses a value, not when one value replaces another.
+fn set_contract_index_type(
+    storage: &mut dyn Storage,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_index_type: Option<IndexType>,
+) -> Result<(), ContractError> {
+    let key = (contract_id, index_name);
+    let has_value = CONTRACT_INDEX_TYPES.has(storage, key);
+    match maybe_index_type {
+        Some(index_type) => {
+            if !has_value {
+                CONTRACT_INDEX_TYPES.save(storage, key, &index_type)?;
+                increment_index_size(storage, index_name, true)?;
+            }
+        },
+        None => {
+            if has_value {
+                CONTRACT_INDEX_TYPES.remove(storage, key);
+                increment_index_size(storage, index_name, false)?;
+            }
+        },
+    }
+    Ok(())
+}
+
 fn increment_index_size(
     storage: &mut dyn Storage,
     index_name: &String,
@@ -365,26 +1604,48 @@ fn increment_index_size(
     Ok(())
 }
 
+fn increment_compound_index_size(
+    storage: &mut dyn Storage,
+    index_name: &String,
+    is_positive: bool,
+) -> Result<(), ContractError> {
+    COMPOUND_INDEX_METADATA.update(
+        storage,
+        index_name.clone(),
+        |maybe_meta| -> Result<_, ContractError> {
+            if let Some(mut meta) = maybe_meta {
+                meta.size = if is_positive {
+                    meta.size.checked_add(Uint64::one())
+                } else {
+                    meta.size.checked_sub(Uint64::one())
+                }
+                .map_err(|_| ContractError::UnexpectedError {
+                    reason: format!("Overflow updating index {} size", index_name),
+                })?;
+                Ok(meta)
+            } else {
+                Err(ContractError::UnexpectedError {
+                    reason: format!("Index {} not found", index_name),
+                })
+            }
+        },
+    )?;
+    Ok(())
+}
+
 fn update_string_index(
     storage: &mut dyn Storage,
     partition: PartitionID,
     contract_id: ContractID,
     index_name: &String,
     maybe_value: &Option<String>,
-    max_str_len: usize,
-    is_case_sensitive: bool,
 ) -> Result<(), ContractError> {
     let index_storage_key = build_index_storage_key(index_name);
     let index: CustomIndexMap<&String> = Map::new(&index_storage_key);
     let indexed_value_map = VALUES_STRING;
 
+    // New values are expected to be normalized already
     if let Some(new_val) = maybe_value {
-        let case_adjusted_new_val = if is_case_sensitive {
-            new_val.clone()
-        } else {
-            new_val.to_lowercase()
-        };
-        let new_val = &pad(&case_adjusted_new_val, max_str_len);
         let index_key = (partition, new_val, contract_id);
         if index.has(storage, index_key) {
             return Ok(());
@@ -405,17 +1666,13 @@ fn update_string_index(
         }
 
         index.save(storage, (partition, new_val, contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, index_name), &IndexType::String)?;
-        }
-        increment_index_size(storage, index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::String))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, index_name))? {
         let index_key = (partition, &old_val, contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, index_name));
-            increment_index_size(storage, index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -456,17 +1713,13 @@ fn update_bool_index(
         }
 
         index.save(storage, index_key, &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Bool)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Bool))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, if old_val { 1u8 } else { 0u8 }, contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -504,21 +1757,13 @@ fn update_timestamp_index(
         }
 
         index.save(storage, (partition, new_val.nanos(), contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(
-                storage,
-                (contract_id, &index_name),
-                &IndexType::Timestamp,
-            )?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Timestamp))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val.nanos(), contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, (partition, old_val.nanos(), contract_id));
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -554,17 +1799,13 @@ fn update_u8_index(
             index.remove(storage, (partition, old_val, contract_id));
         }
         index.save(storage, (partition, *new_val, contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Uint8)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Uint8))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val, contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -602,17 +1843,13 @@ fn update_u16_index(
         }
 
         index.save(storage, (partition, *new_val, contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Uint16)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Uint16))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val, contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -648,17 +1885,13 @@ fn update_i32_index(
             index.remove(storage, (partition, old_val, contract_id));
         }
         index.save(storage, (partition, *new_val, contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Int32)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Int32))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val, contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -694,17 +1927,13 @@ fn update_u32_index(
             index.remove(storage, (partition, old_val, contract_id));
         }
         index.save(storage, (partition, *new_val, contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Uint32)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Uint32))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val, contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -740,17 +1969,13 @@ fn update_u64_index(
             index.remove(storage, (partition, old_val.into(), contract_id));
         }
         index.save(storage, (partition, (*new_val).into(), contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Uint64)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Uint64))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val.u64(), contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -788,17 +2013,13 @@ fn update_u128_index(
         }
 
         index.save(storage, (partition, (*new_val).into(), contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Uint128)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Uint128))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val.u128(), contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
@@ -836,18 +2057,541 @@ fn update_binary_index(
         }
 
         index.save(storage, (partition, new_val.as_slice(), contract_id), &X)?;
-        if !CONTRACT_INDEX_TYPES.has(storage, (contract_id, &index_name)) {
-            CONTRACT_INDEX_TYPES.save(storage, (contract_id, &index_name), &IndexType::Uint128)?;
-        }
-        increment_index_size(storage, &index_name, true)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Binary))?;
     } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
         let index_key = (partition, old_val.as_slice(), contract_id);
         if index.has(storage, index_key) {
             index.remove(storage, index_key);
             indexed_value_map.remove(storage, (contract_id, index_name));
-            CONTRACT_INDEX_TYPES.remove(storage, (contract_id, &index_name));
-            increment_index_size(storage, &index_name, false)?;
+            set_contract_index_type(storage, contract_id, index_name, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_i64_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: &Option<i64>,
+) -> Result<(), ContractError> {
+    let index_slot = build_index_storage_key(index_name);
+    let index: CustomIndexMap<i64> = Map::new(&index_slot);
+    let indexed_value_map = VALUES_I64;
+    let mut maybe_old_val: Option<i64> = None;
+
+    if let Some(new_val) = maybe_value {
+        let index_key = (partition, *new_val, contract_id);
+        if index.has(storage, index_key) {
+            return Ok(());
+        }
+
+        indexed_value_map.update(
+            storage,
+            (contract_id, &index_name.to_owned()),
+            |x| -> Result<_, ContractError> {
+                maybe_old_val = x;
+                Ok(*new_val)
+            },
+        )?;
+
+        if let Some(old_val) = &maybe_old_val {
+            index.remove(storage, (partition, *old_val, contract_id));
+        }
+
+        index.save(storage, (partition, *new_val, contract_id), &X)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Int64))?;
+    } else if let Some(old_val) =
+        &indexed_value_map.may_load(storage, (contract_id, &index_name))?
+    {
+        let index_key = (partition, *old_val, contract_id);
+        if index.has(storage, index_key) {
+            index.remove(storage, index_key);
+            indexed_value_map.remove(storage, (contract_id, index_name));
+            set_contract_index_type(storage, contract_id, index_name, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_i128_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: &Option<i128>,
+) -> Result<(), ContractError> {
+    let index_slot = build_index_storage_key(index_name);
+    let index: CustomIndexMap<i128> = Map::new(&index_slot);
+    let indexed_value_map = VALUES_I128;
+    let mut maybe_old_val: Option<i128> = None;
+
+    if let Some(new_val) = maybe_value {
+        let index_key = (partition, *new_val, contract_id);
+        if index.has(storage, index_key) {
+            return Ok(());
+        }
+
+        indexed_value_map.update(
+            storage,
+            (contract_id, &index_name.to_owned()),
+            |x| -> Result<_, ContractError> {
+                maybe_old_val = x;
+                Ok(*new_val)
+            },
+        )?;
+
+        if let Some(old_val) = &maybe_old_val {
+            index.remove(storage, (partition, *old_val, contract_id));
+        }
+
+        index.save(storage, (partition, *new_val, contract_id), &X)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Int128))?;
+    } else if let Some(old_val) =
+        &indexed_value_map.may_load(storage, (contract_id, &index_name))?
+    {
+        let index_key = (partition, *old_val, contract_id);
+        if index.has(storage, index_key) {
+            index.remove(storage, index_key);
+            indexed_value_map.remove(storage, (contract_id, index_name));
+            set_contract_index_type(storage, contract_id, index_name, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_decimal_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: &Option<Decimal>,
+) -> Result<(), ContractError> {
+    let index_slot = build_index_storage_key(index_name);
+    let index: CustomIndexMap<u128> = Map::new(&index_slot);
+    let indexed_value_map = VALUES_DECIMAL;
+    let mut maybe_old_val: Option<Decimal> = None;
+
+    if let Some(new_val) = maybe_value {
+        let index_key = (partition, new_val.atomics().u128(), contract_id);
+        if index.has(storage, index_key) {
+            return Ok(());
+        }
+
+        indexed_value_map.update(
+            storage,
+            (contract_id, &index_name.to_owned()),
+            |x| -> Result<_, ContractError> {
+                maybe_old_val = x;
+                Ok(*new_val)
+            },
+        )?;
+
+        if let Some(old_val) = &maybe_old_val {
+            index.remove(storage, (partition, old_val.atomics().u128(), contract_id));
+        }
+
+        index.save(
+            storage,
+            (partition, new_val.atomics().u128(), contract_id),
+            &X,
+        )?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Decimal))?;
+    } else if let Some(old_val) =
+        &indexed_value_map.may_load(storage, (contract_id, &index_name))?
+    {
+        let index_key = (partition, old_val.atomics().u128(), contract_id);
+        if index.has(storage, index_key) {
+            index.remove(storage, index_key);
+            indexed_value_map.remove(storage, (contract_id, index_name));
+            set_contract_index_type(storage, contract_id, index_name, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_decimal256_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: &Option<Decimal256>,
+) -> Result<(), ContractError> {
+    let index_slot = build_index_storage_key(index_name);
+    let index: CustomIndexMap<&[u8]> = Map::new(&index_slot);
+    let indexed_value_map = VALUES_DECIMAL256;
+    let mut maybe_old_val: Option<Decimal256> = None;
+
+    // Index keys are the big-endian bytes of each decimal's atomics
+    if let Some(new_val) = maybe_value {
+        let new_bytes = new_val.atomics().to_be_bytes();
+        let index_key = (partition, new_bytes.as_slice(), contract_id);
+        if index.has(storage, index_key) {
+            return Ok(());
+        }
+
+        indexed_value_map.update(
+            storage,
+            (contract_id, &index_name.to_owned()),
+            |x| -> Result<_, ContractError> {
+                maybe_old_val = x;
+                Ok(*new_val)
+            },
+        )?;
+
+        if let Some(old_val) = &maybe_old_val {
+            let old_bytes = old_val.atomics().to_be_bytes();
+            index.remove(storage, (partition, old_bytes.as_slice(), contract_id));
+        }
+
+        index.save(storage, index_key, &X)?;
+        set_contract_index_type(
+            storage,
+            contract_id,
+            index_name,
+            Some(IndexType::Decimal256),
+        )?;
+    } else if let Some(old_val) = indexed_value_map.may_load(storage, (contract_id, &index_name))? {
+        let old_bytes = old_val.atomics().to_be_bytes();
+        let index_key = (partition, old_bytes.as_slice(), contract_id);
+        if index.has(storage, index_key) {
+            index.remove(storage, index_key);
+            indexed_value_map.remove(storage, (contract_id, index_name));
+            set_contract_index_type(storage, contract_id, index_name, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_addr_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: &Option<Addr>,
+) -> Result<(), ContractError> {
+    let index_slot = build_index_storage_key(index_name);
+    let index: CustomIndexMap<&Addr> = Map::new(&index_slot);
+    let indexed_value_map = VALUES_ADDR;
+    let mut maybe_old_val: Option<Addr> = None;
+
+    if let Some(new_val) = maybe_value {
+        let index_key = (partition, new_val, contract_id);
+        if index.has(storage, index_key) {
+            return Ok(());
+        }
+
+        indexed_value_map.update(
+            storage,
+            (contract_id, &index_name.to_owned()),
+            |x| -> Result<_, ContractError> {
+                maybe_old_val = x;
+                Ok(new_val.clone())
+            },
+        )?;
+
+        if let Some(old_val) = &maybe_old_val {
+            index.remove(storage, (partition, old_val, contract_id));
+        }
+
+        index.save(storage, (partition, new_val, contract_id), &X)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Addr))?;
+    } else if let Some(old_val) =
+        &indexed_value_map.may_load(storage, (contract_id, &index_name))?
+    {
+        let index_key = (partition, old_val, contract_id);
+        if index.has(storage, index_key) {
+            index.remove(storage, index_key);
+            indexed_value_map.remove(storage, (contract_id, index_name));
+            set_contract_index_type(storage, contract_id, index_name, None)?;
+        }
+    }
+    Ok(())
+}
+
+fn update_geo_index(
+    storage: &mut dyn Storage,
+    partition: PartitionID,
+    contract_id: ContractID,
+    index_name: &String,
+    maybe_value: &Option<GeoPoint>,
+) -> Result<(), ContractError> {
+    let index_slot = build_index_storage_key(index_name);
+    let index: CustomIndexMap<&str> = Map::new(&index_slot);
+    let indexed_value_map = VALUES_GEO;
+    let mut maybe_old_val: Option<GeoPoint> = None;
+
+    // Index keys are the geohash of each point, so nearby points share prefixes
+    if let Some(new_val) = maybe_value {
+        let new_hash = geohash(new_val, GEOHASH_PRECISION);
+        let index_key = (partition, new_hash.as_str(), contract_id);
+
+        // Distinct points can share a geohash, so an existing entry only means
+        // there's nothing to do if the stored point is the same.
+        if index.has(storage, index_key)
+            && indexed_value_map
+                .may_load(storage, (contract_id, index_name))?
+                .as_ref()
+                == Some(new_val)
+        {
+            return Ok(());
+        }
+
+        indexed_value_map.update(
+            storage,
+            (contract_id, &index_name.to_owned()),
+            |x| -> Result<_, ContractError> {
+                maybe_old_val = x;
+                Ok(new_val.clone())
+            },
+        )?;
+
+        if let Some(old_val) = &maybe_old_val {
+            let old_hash = geohash(old_val, GEOHASH_PRECISION);
+            index.remove(storage, (partition, old_hash.as_str(), contract_id));
+        }
+
+        index.save(storage, index_key, &X)?;
+        set_contract_index_type(storage, contract_id, index_name, Some(IndexType::Geo))?;
+    } else if let Some(old_val) =
+        &indexed_value_map.may_load(storage, (contract_id, &index_name))?
+    {
+        let old_hash = geohash(old_val, GEOHASH_PRECISION);
+        let index_key = (partition, old_hash.as_str(), contract_id);
+        if index.has(storage, index_key) {
+            index.remove(storage, index_key);
+            indexed_value_map.remove(storage, (contract_id, index_name));
+            set_contract_index_type(storage, contract_id, index_name, None)?;
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::MemoryStorage;
+
+    fn create_index(
+        storage: &mut dyn Storage,
+        name: &str,
+        unique: Option<UniquenessScope>,
+        multi: bool,
+    ) {
+        let meta = IndexMetadata {
+            index_type: IndexType::Binary,
+            name: name.to_owned(),
+            size: Uint64::zero(),
+            constraints: None,
+            unique,
+            strings: None,
+            multi: Some(multi),
+            facets: None,
+            text: None,
+            deletion: None,
+        };
+        INDEX_METADATA
+            .save(storage, name.to_owned(), &meta)
+            .unwrap();
+    }
+
+    fn set_value(
+        storage: &mut dyn Storage,
+        partition: PartitionID,
+        contract_id: ContractID,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), ContractError> {
+        let kv = KeyValue::Binary(name.to_owned(), value.map(|v| Binary::from(v.as_bytes())));
+        update_indices(
+            &MockApi::default(),
+            storage,
+            partition,
+            contract_id,
+            vec![kv],
+        )
+    }
+
+    fn load_owner(
+        storage: &dyn Storage,
+        name: &str,
+        partition: Option<PartitionID>,
+        value: &str,
+    ) -> Option<ContractID> {
+        let mut key = partition.map_or(vec![], |p| p.to_be_bytes().to_vec());
+        key.extend(encode_ordered(&IndexValue::Binary(Binary::from(
+            value.as_bytes(),
+        ))));
+        INDEX_UNIQUE_VALUES
+            .may_load(storage, (&name.to_owned(), key))
+            .unwrap()
+    }
+
+    fn load_size(
+        storage: &dyn Storage,
+        name: &str,
+    ) -> u64 {
+        INDEX_METADATA
+            .load(storage, name.to_owned())
+            .unwrap()
+            .size
+            .u64()
+    }
+
+    fn update_set(
+        storage: &mut dyn Storage,
+        partition: PartitionID,
+        contract_id: ContractID,
+        name: &str,
+        remove: &[&str],
+        add: &[&str],
+    ) -> Result<(), ContractError> {
+        let kv = KeyValue::Set(
+            name.to_owned(),
+            IndexSetUpdates {
+                remove: Some(elements(remove)),
+                add: Some(elements(add)),
+            },
+        );
+        update_indices(
+            &MockApi::default(),
+            storage,
+            partition,
+            contract_id,
+            vec![kv],
+        )
+    }
+
+    fn elements(values: &[&str]) -> Vec<IndexValue> {
+        values
+            .iter()
+            .map(|v| IndexValue::Binary(Binary::from(v.as_bytes())))
+            .collect()
+    }
+
+    #[test]
+    fn unique_values_are_claimed_across_the_table() {
+        let mut storage = MemoryStorage::new();
+        let name = "code";
+        create_index(&mut storage, name, Some(UniquenessScope::Table), false);
+
+        set_value(&mut storage, 1, 1, name, Some("a")).unwrap();
+        assert_eq!(load_owner(&storage, name, None, "a"), Some(1));
+
+        // Contracts in other partitions can't claim the same value
+        let err = set_value(&mut storage, 2, 2, name, Some("a")).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UniqueIndexConflict { contract_id: 1, .. }
+        ));
+
+        // Replacing a value releases its claim
+        set_value(&mut storage, 1, 1, name, Some("b")).unwrap();
+        assert_eq!(load_owner(&storage, name, None, "a"), None);
+        assert_eq!(load_owner(&storage, name, None, "b"), Some(1));
+        set_value(&mut storage, 2, 2, name, Some("a")).unwrap();
+        assert_eq!(load_owner(&storage, name, None, "a"), Some(2));
+
+        // Setting a contract's own value again keeps its claim
+        set_value(&mut storage, 2, 2, name, Some("a")).unwrap();
+        assert_eq!(load_owner(&storage, name, None, "a"), Some(2));
+        assert_eq!(load_size(&storage, name), 2);
+    }
+
+    #[test]
+    fn unique_values_are_claimed_per_partition() {
+        let mut storage = MemoryStorage::new();
+        let name = "code";
+        create_index(&mut storage, name, Some(UniquenessScope::Partition), false);
+
+        set_value(&mut storage, 1, 1, name, Some("a")).unwrap();
+        set_value(&mut storage, 2, 2, name, Some("a")).unwrap();
+        assert_eq!(load_owner(&storage, name, Some(1), "a"), Some(1));
+        assert_eq!(load_owner(&storage, name, Some(2), "a"), Some(2));
+
+        let err = set_value(&mut storage, 1, 3, name, Some("a")).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UniqueIndexConflict { contract_id: 1, .. }
+        ));
+
+        // Removing a value releases its claim
+        set_value(&mut storage, 1, 1, name, None).unwrap();
+        assert_eq!(load_owner(&storage, name, Some(1), "a"), None);
+        set_value(&mut storage, 1, 3, name, Some("a")).unwrap();
+        assert_eq!(load_owner(&storage, name, Some(1), "a"), Some(3));
+        assert_eq!(load_owner(&storage, name, Some(2), "a"), Some(2));
+        assert_eq!(load_size(&storage, name), 2);
+    }
+
+    #[test]
+    fn unique_claims_are_released_on_archive_and_taken_on_restore() {
+        let mut storage = MemoryStorage::new();
+        let name = "code";
+        create_index(&mut storage, name, Some(UniquenessScope::Table), false);
+        set_value(&mut storage, 1, 1, name, Some("a")).unwrap();
+
+        // Archiving removes a contract's entries but keeps its stored value
+        set_custom_index_entries(&mut storage, 1, 1, false).unwrap();
+        assert_eq!(load_owner(&storage, name, None, "a"), None);
+        assert_eq!(load_size(&storage, name), 0);
+        assert!(CONTRACT_INDEX_TYPES.has(&storage, (1, &name.to_owned())));
+
+        // Restoring fails while another contract holds the value
+        set_value(&mut storage, 1, 2, name, Some("a")).unwrap();
+        let err = set_custom_index_entries(&mut storage, 1, 1, true).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UniqueIndexConflict { contract_id: 2, .. }
+        ));
+
+        set_value(&mut storage, 1, 2, name, None).unwrap();
+        set_custom_index_entries(&mut storage, 1, 1, true).unwrap();
+        assert_eq!(load_owner(&storage, name, None, "a"), Some(1));
+        assert_eq!(load_size(&storage, name), 1);
+    }
+
+    #[test]
+    fn set_elements_are_added_removed_and_counted() {
+        let mut storage = MemoryStorage::new();
+        let name = "labels";
+        let index_name = name.to_owned();
+        create_index(&mut storage, name, None, true);
+
+        update_set(&mut storage, 1, 1, name, &[], &["a", "b"]).unwrap();
+        assert_eq!(load_size(&storage, name), 2);
+        assert!(CONTRACT_INDEX_TYPES.has(&storage, (1, &index_name)));
+
+        // Adding an element that's present or removing one that isn't does
+        // nothing
+        update_set(&mut storage, 1, 1, name, &["c"], &["b"]).unwrap();
+        assert_eq!(load_size(&storage, name), 2);
+
+        update_set(&mut storage, 1, 1, name, &["a"], &["c"]).unwrap();
+        assert_eq!(
+            load_index_set_values(&storage, 1, &index_name).unwrap(),
+            elements(&["b", "c"])
+        );
+        assert_eq!(load_size(&storage, name), 2);
+
+        // Each contract's elements have their own entries
+        update_set(&mut storage, 1, 2, name, &[], &["b"]).unwrap();
+        assert_eq!(load_size(&storage, name), 3);
+        let index_storage_key = build_index_storage_key(&index_name);
+        let index: CustomIndexMap<&[u8]> = Map::new(&index_storage_key);
+        assert!(!index.has(&storage, (1, b"a".as_slice(), 1)));
+        assert!(index.has(&storage, (1, b"b".as_slice(), 1)));
+        assert!(index.has(&storage, (1, b"b".as_slice(), 2)));
+
+        // Removing the last element drops the contract's value
+        update_set(&mut storage, 1, 1, name, &["b", "c"], &[]).unwrap();
+        assert_eq!(load_size(&storage, name), 1);
+        assert!(!CONTRACT_INDEX_TYPES.has(&storage, (1, &index_name)));
+        assert!(load_index_set_values(&storage, 1, &index_name)
+            .unwrap()
+            .is_empty());
+        assert!(index.has(&storage, (1, b"b".as_slice(), 2)));
+    }
+}
@@ -15,4 +15,6 @@ pub mod msg;
 pub mod query;
 pub mod state;
 #[cfg(not(feature = "library"))]
+mod transaction;
+#[cfg(not(feature = "library"))]
 mod util;
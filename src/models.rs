@@ -1,8 +1,8 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp, Uint64};
+use cosmwasm_std::{Addr, Timestamp, Uint256, Uint64};
 
 use crate::{
-    msg::CreationParams,
+    msg::{BatchCreationResult, CreationParams, ExpirySettings},
     state::{GroupID, PartitionID},
 };
 
@@ -15,6 +15,17 @@ pub struct ContractFlag {
     pub time: Timestamp,
 }
 
+/// Running count and sum of the values of a numeric custom index. Negative
+/// values are summed separately, so that both sums stay unsigned. The sums of
+/// decimal indices are in atomics.
+#[cw_serde]
+#[derive(Default)]
+pub struct IndexAggregate {
+    pub count: Uint64,
+    pub sum_positive: Uint256,
+    pub sum_negative: Uint256,
+}
+
 #[cw_serde]
 pub struct ContractMetadata {
     pub id: Uint64,
@@ -23,6 +34,9 @@ pub struct ContractMetadata {
     pub created_at: Timestamp,
     pub created_at_height: Uint64,
     pub is_managed: bool,
+    // Wasm admin as of creation, registration or the last refresh. Not set for
+    // contracts indexed before admins were recorded.
+    pub admin: Option<Addr>,
     pub partition: PartitionID,
 }
 
@@ -30,6 +44,7 @@ pub struct ContractMetadata {
 pub struct ContractMetadataViewDetails {
     pub id: Uint64,
     pub is_managed: bool,
+    pub admin: Option<Addr>,
     pub code_id: Uint64,
     pub created_by: Addr,
     pub created_at_height: Uint64,
@@ -61,9 +76,39 @@ pub enum ReplyJob {
     Create {
         params: CreationParams,
         initiator: Addr,
+        batch_id: Option<u64>,
     },
 }
 
+#[cw_serde]
+pub struct FailedReplyJob {
+    pub job: ReplyJob,
+    pub reason: String,
+    pub failed_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct CreateBatch {
+    pub best_effort: bool,
+    pub size: u32,
+    pub results: Vec<BatchCreationResult>,
+}
+
+#[cw_serde]
+pub struct ContractTombstone {
+    pub address: Addr,
+    pub meta: ContractMetadata,
+    pub dyn_meta: Option<DynamicContractMetadata>,
+    pub expiry: Option<ExpirySettings>,
+    // Padded tags with their uniqueness flags
+    pub tags: Vec<(String, u8)>,
+    pub groups: Vec<GroupID>,
+    // Padded relationship names and related addresses with uniqueness flags
+    pub relationships: Vec<(String, String, u8)>,
+    pub archived_at: Timestamp,
+    pub archived_by: Addr,
+}
+
 #[cw_serde]
 pub enum Details {
     Basic,
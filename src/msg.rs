@@ -1,10 +1,11 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Api, Binary, Timestamp, Uint128, Uint64};
+use cosmwasm_std::{Addr, Api, Binary, Decimal, Decimal256, Timestamp, Uint128, Uint64};
 use cw_lib::models::Owner;
+use cw_utils::Expiration;
 
 use crate::{
     error::ContractError,
-    models::{ContractMetadataView, Details},
+    models::{ContractMetadataView, ContractTombstone, Details, ReplyJob},
     state::{GroupID, PartitionID},
 };
 
@@ -24,21 +25,36 @@ pub enum AdminMsg {
     CreateGroup(GroupCreationParams),
     CreatePartition(PartitionCreationParams),
     CreateIndex(IndexCreationParams),
+    CreateCompoundIndex(CompoundIndexCreationParams),
     UpdateInfo(TableInfo),
     SetPartition(Addr, PartitionSelector),
     AssignGroups(GroupUpdates),
     UpdateConfig(Config),
     RevertConfig(),
     Unsuspend(Addr),
-    DeleteIndex(String),
+    DeleteIndex(IndexDeletionParams),
+    DeleteCompoundIndex(IndexDeletionParams),
+    Reindex(ReindexParams),
+    IndexManagedContracts(ManagedIndexingParams),
     SetOwner(Owner),
+    ClearReplyJobs(ReplyJobClearParams),
+    MigrateContracts(ContractsMigrationParams),
+    Restore(Addr),
+    UpdateContractAdmin(ContractAdminUpdateParams),
+    ClearContractAdmin(ContractAdminClearParams),
 }
 
 #[cw_serde]
 pub enum ClientMsg {
+    // Reverts with CreateError if the instantiate fails
     Create(CreationParams),
+    CreateMany(BatchCreationParams),
+    Register(RegistrationParams),
+    Refresh(RefreshParams),
     Update(UpdateParams),
     Delete(Addr),
+    Archive(Addr),
+    Expire(ExpiryCrankParams),
     Flag(FlagParams),
 }
 
@@ -70,14 +86,23 @@ pub enum ContractsQueryMsg {
     InGroup(GroupQueryParams),
     ByAddresses(AddressesQueryParams),
     RelatedTo(RelationshipQueryParams),
+    Drifted(DriftQueryParams),
+    Archived(ArchivedQueryParams),
 }
 
 #[cw_serde]
 pub enum TableQueryMsg {
     Indices(TableIndicesQueryParams),
+    CompoundIndices(TableIndicesQueryParams),
+    ReindexStatus(String),
+    ManagedIndexingStatus {},
+    Aggregates(IndexAggregatesQueryParams),
+    Facets(IndexFacetsQueryParams),
     Partitions(TablePartitionsQueryParams),
     Groups(TableGroupsQueryParams),
     Tags(TableTagsQueryParams),
+    PredictAddress(AddressPredictionParams),
+    ReplyJobs(TableReplyJobsQueryParams),
 }
 
 #[cw_serde]
@@ -86,6 +111,7 @@ pub enum ContractQueryMsg {
     Groups(ContractGroupsQueryParams),
     Tags(ContractTagsQueryParams),
     IsRelatedTo(ContractIsRelatedToParams),
+    Values(ContractValuesQueryParams),
 }
 
 #[cw_serde]
@@ -101,6 +127,8 @@ pub enum MigrateMsg {
         string_indices: Vec<String>,
         use_lifecycle_hooks: bool,
     },
+    // Remove reply jobs left over from before replies removed their jobs
+    ClearStaleReplyJobs {},
 }
 
 #[cw_serde]
@@ -109,6 +137,60 @@ pub struct IndicesResponse {
     pub cursor: Option<String>,
 }
 
+#[cw_serde]
+pub struct CompoundIndicesResponse {
+    pub indices: Vec<CompoundIndexMetadata>,
+    pub cursor: Option<String>,
+}
+
+#[cw_serde]
+pub struct IndexAggregatesResponse {
+    pub count: Uint64,
+    pub sum: String,
+    pub average: Option<String>,
+    pub min: Option<IndexValue>,
+    pub max: Option<IndexValue>,
+}
+
+#[cw_serde]
+pub struct IndexFacet {
+    pub value: IndexValue,
+    pub count: u32,
+}
+
+#[cw_serde]
+pub struct IndexFacetsResponse {
+    pub facets: Vec<IndexFacet>,
+    pub cursor: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct ReplyJobView {
+    pub id: Uint64,
+    pub status: ReplyJobStatus,
+    pub job: ReplyJob,
+    pub reason: Option<String>,
+    pub failed_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct ReplyJobsResponse {
+    pub jobs: Vec<ReplyJobView>,
+    pub cursor: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct CreationResponse {
+    pub address: Addr,
+    pub id: Uint64,
+}
+
+#[cw_serde]
+pub struct PredictedAddressResponse {
+    pub address: Addr,
+    pub exists: bool,
+}
+
 #[cw_serde]
 pub struct ContractIsRelatedToResponse {
     pub is_related: bool,
@@ -220,6 +302,12 @@ pub struct ContractGroupsResponse {
     pub cursor: Option<GroupID>,
 }
 
+#[cw_serde]
+pub struct ContractValuesResponse {
+    pub values: Vec<(String, IndexValue)>,
+    pub cursor: Option<String>,
+}
+
 #[cw_serde]
 pub struct ContractTagsResponse {
     pub tags: Vec<String>,
@@ -238,11 +326,74 @@ pub struct CreationParams {
     pub code_id: Uint64,
     pub instantiate_msg: Binary,
     pub admin: Option<Addr>,
+    pub salt: Option<Binary>,
     // Internal contract params
     pub partition: PartitionSelector,
     pub label: Option<String>,
     pub groups: Option<Vec<GroupID>>,
     pub tags: Option<Vec<String>>,
+    // Tags with options, such as uniqueness, added along with any plain tags
+    pub tag_options: Option<Vec<TagUpdate>>,
+    pub values: Option<Vec<KeyValue>>,
+    pub relationships: Option<Vec<Relationship>>,
+    pub use_lifecycle_hooks: Option<bool>,
+    pub expiry: Option<ExpirySettings>,
+}
+
+#[cw_serde]
+pub enum BatchMode {
+    // Revert the entire batch if any contract fails to instantiate
+    Atomic,
+    // Keep the contracts that succeed and report the ones that fail, either to
+    // instantiate or to be registered with their initial values
+    BestEffort,
+}
+
+#[cw_serde]
+pub struct BatchCreationParams {
+    pub items: Vec<CreationParams>,
+    pub mode: Option<BatchMode>,
+}
+
+#[cw_serde]
+pub struct BatchCreationResult {
+    pub index: u32,
+    pub address: Option<Addr>,
+    pub id: Option<Uint64>,
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct BatchCreationResponse {
+    pub results: Vec<BatchCreationResult>,
+}
+
+#[cw_serde]
+pub enum ExpiryAction {
+    Archive,
+    Delete,
+}
+
+#[cw_serde]
+pub struct ExpirySettings {
+    // Expiration::Never clears any existing expiry
+    pub expires: Expiration,
+    // Defaults to archiving the record
+    pub action: Option<ExpiryAction>,
+}
+
+#[cw_serde]
+pub struct ExpiryCrankParams {
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct RegistrationParams {
+    // Address of an existing contract, instantiated outside of the table
+    pub contract: Addr,
+    // Internal contract params
+    pub partition: PartitionSelector,
+    pub groups: Option<Vec<GroupID>>,
     pub use_lifecycle_hooks: Option<bool>,
 }
 
@@ -259,6 +410,7 @@ pub struct UpdateParams {
     pub values: Option<Vec<KeyValue>>,
     pub tags: Option<TagUpdates>,
     pub relationships: Option<RelationshipUpdates>,
+    pub expiry: Option<ExpirySettings>,
 }
 
 #[cw_serde]
@@ -273,6 +425,42 @@ pub enum KeyValue {
     Uint64(String, Option<Uint64>),
     Uint128(String, Option<Uint128>),
     Binary(String, Option<Binary>),
+    Int64(String, Option<i64>),
+    Int128(String, Option<i128>),
+    Decimal(String, Option<Decimal>),
+    Decimal256(String, Option<Decimal256>),
+    Addr(String, Option<Addr>),
+    Geo(String, Option<GeoPoint>),
+    Set(String, IndexSetUpdates),
+}
+
+/// A location on the earth's surface, in integer microdegrees of latitude and
+/// longitude.
+#[cw_serde]
+pub struct GeoPoint {
+    pub lat: i32,
+    pub lon: i32,
+}
+
+/// An area to search for in a geo index. A bounding box whose west edge lies
+/// east of its east edge crosses the antimeridian.
+#[cw_serde]
+pub enum GeoArea {
+    BoundingBox {
+        south_west: GeoPoint,
+        north_east: GeoPoint,
+    },
+    Radius {
+        center: GeoPoint,
+        meters: u32,
+    },
+}
+
+/// Elements to add to and remove from the values of a set-valued index.
+#[cw_serde]
+pub struct IndexSetUpdates {
+    pub remove: Option<Vec<IndexValue>>,
+    pub add: Option<Vec<IndexValue>>,
 }
 
 #[cw_serde]
@@ -287,6 +475,12 @@ pub enum IndexValue {
     Uint64(Uint64),
     Uint128(Uint128),
     Binary(Binary),
+    Int64(i64),
+    Int128(i128),
+    Decimal(Decimal),
+    Decimal256(Decimal256),
+    Addr(Addr),
+    Geo(GeoPoint),
 }
 
 #[cw_serde]
@@ -301,6 +495,11 @@ pub enum IndexValueRange {
     Uint64(Uint64, Uint64),
     Uint128(Uint128, Uint128),
     Binary(Binary, Binary),
+    Int64(i64, i64),
+    Int128(i128, i128),
+    Decimal(Decimal, Decimal),
+    Decimal256(Decimal256, Decimal256),
+    Addr(Addr, Addr),
 }
 
 #[cw_serde]
@@ -312,6 +511,7 @@ pub enum RangeSelector {
     CodeId,
     Id,
     Rev,
+    IsManaged,
     String(String),
     Bool(String),
     Timestamp(String),
@@ -322,6 +522,13 @@ pub enum RangeSelector {
     Uint64(String),
     Uint128(String),
     Binary(String),
+    Int64(String),
+    Int128(String),
+    Decimal(String),
+    Decimal256(String),
+    Addr(String),
+    Geo(String),
+    Compound(String),
 }
 
 #[cw_serde]
@@ -336,6 +543,108 @@ pub enum IndexType {
     Uint64,
     Uint128,
     Binary,
+    Int64,
+    Int128,
+    Decimal,
+    Decimal256,
+    Addr,
+    Geo,
+}
+
+#[cw_serde]
+pub enum ContractSelector {
+    CodeId(PartitionID, Uint64),
+    Partition(PartitionID),
+    Group(GroupID),
+    Addresses(Vec<Addr>),
+}
+
+#[cw_serde]
+pub struct ContractsMigrationParams {
+    pub select: ContractSelector,
+    pub code_id: Uint64,
+    pub msg: Binary,
+    pub cursor: Option<Uint64>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ContractsMigrationResponse {
+    pub migrated: u32,
+    pub skipped: u32,
+    pub cursor: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct RefreshParams {
+    pub select: ContractSelector,
+    pub cursor: Option<Uint64>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct RefreshResponse {
+    pub refreshed: u32,
+    pub cursor: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct DriftQueryParams {
+    pub select: ContractSelector,
+    pub cursor: Option<Uint64>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ContractDrift {
+    pub address: Addr,
+    pub id: Uint64,
+    pub stored_code_id: Uint64,
+    pub code_id: Uint64,
+    pub stored_admin: Option<Addr>,
+    pub admin: Option<Addr>,
+    pub is_managed: bool,
+}
+
+#[cw_serde]
+pub struct ArchivedQueryParams {
+    pub cursor: Option<Uint64>,
+    pub desc: Option<bool>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ArchivedContractsResponse {
+    pub contracts: Vec<ContractTombstone>,
+    pub cursor: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct ContractsDriftResponse {
+    pub contracts: Vec<ContractDrift>,
+    pub cursor: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct ContractAdminUpdateParams {
+    pub select: ContractSelector,
+    pub admin: Addr,
+    pub cursor: Option<Uint64>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ContractAdminClearParams {
+    pub select: ContractSelector,
+    pub cursor: Option<Uint64>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ContractAdminUpdateResponse {
+    pub updated: u32,
+    pub skipped: u32,
+    pub cursor: Option<Uint64>,
 }
 
 #[cw_serde]
@@ -362,6 +671,70 @@ pub struct IndexMetadata {
     pub index_type: IndexType,
     pub name: String,
     pub size: Uint64,
+    pub constraints: Option<IndexConstraints>,
+    pub unique: Option<UniquenessScope>,
+    pub strings: Option<StringIndexSettings>,
+    pub multi: Option<bool>,
+    pub facets: Option<bool>,
+    pub text: Option<bool>,
+    // Set while the index is being deleted
+    pub deletion: Option<IndexDeletionProgress>,
+}
+
+#[cw_serde]
+pub struct IndexDeletionProgress {
+    // ID of the last contract scanned
+    pub cursor: Option<Uint64>,
+    pub removed: Uint64,
+    pub started_at: Timestamp,
+}
+
+/// Scope within which no two contracts may share a value of a unique index.
+#[cw_serde]
+pub enum UniquenessScope {
+    Partition,
+    Table,
+}
+
+/// Normalization rules for the values of a string index. Settings left unset
+/// when the index is created are copied from the table's global config.
+#[cw_serde]
+#[derive(Default)]
+pub struct StringIndexSettings {
+    pub case_sensitive: Option<bool>,
+    // Length in bytes to which stored values are padded
+    pub max_len: Option<u16>,
+    pub overflow: Option<StringOverflowPolicy>,
+}
+
+/// What to do with string values longer than an index's max length.
+#[cw_serde]
+pub enum StringOverflowPolicy {
+    Reject,
+    Truncate,
+}
+
+/// Constraints on the values written to a custom index. For string and binary
+/// indices, min and max bound the length of the value. For other types, they
+/// bound the value itself, using the same string formats as range queries.
+#[cw_serde]
+#[derive(Default)]
+pub struct IndexConstraints {
+    // Require a value when a contract is created
+    pub required: Option<bool>,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    // Glob pattern for string values, where '*' matches any sequence of
+    // characters and '?' matches a single character
+    pub pattern: Option<String>,
+}
+
+#[cw_serde]
+pub struct CompoundIndexMetadata {
+    pub name: String,
+    pub components: Vec<String>,
+    pub size: Uint64,
+    pub deletion: Option<IndexDeletionProgress>,
 }
 #[cw_serde]
 pub struct TagUpdate {
@@ -439,6 +812,27 @@ pub struct ContractGroupsQueryParams {
     pub partition: PartitionID,
 }
 
+#[cw_serde]
+pub enum AggregateScope {
+    Partition(PartitionID),
+    Group(GroupID),
+}
+
+#[cw_serde]
+pub struct IndexAggregatesQueryParams {
+    pub index: String,
+    pub scope: AggregateScope,
+}
+
+#[cw_serde]
+pub struct IndexFacetsQueryParams {
+    pub index: String,
+    pub partition: PartitionID,
+    pub cursor: Option<Binary>,
+    pub desc: Option<bool>,
+    pub limit: Option<u32>,
+}
+
 #[cw_serde]
 pub struct ContractTagsQueryParams {
     pub contract: Addr,
@@ -448,6 +842,16 @@ pub struct ContractTagsQueryParams {
     pub partition: PartitionID,
 }
 
+#[cw_serde]
+pub struct ContractValuesQueryParams {
+    pub contract: Addr,
+    // Only return values of these indices
+    pub keys: Option<Vec<String>>,
+    pub cursor: Option<String>,
+    pub desc: Option<bool>,
+    pub limit: Option<u32>,
+}
+
 #[cw_serde]
 pub struct ContractRelationshipsQueryParams {
     pub contract: Addr,
@@ -496,6 +900,67 @@ pub struct TableTagsQueryParams {
     pub partition: PartitionID,
 }
 
+#[cw_serde]
+pub enum ReplyJobStatus {
+    // Best-effort batch item that failed, kept for inspection. A single
+    // Create whose instantiate fails still reverts with CreateError.
+    Failed,
+}
+
+#[cw_serde]
+pub struct TableReplyJobsQueryParams {
+    pub status: ReplyJobStatus,
+    pub cursor: Option<Uint64>,
+    pub desc: Option<bool>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ReplyJobClearParams {
+    pub status: ReplyJobStatus,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ReindexParams {
+    // Name of a custom or compound index
+    pub index: String,
+    pub limit: Option<u32>,
+    // Start over from the first contract, discarding any saved progress
+    pub restart: Option<bool>,
+}
+
+#[cw_serde]
+pub struct ReindexProgress {
+    pub index: String,
+    // ID of the last contract scanned
+    pub cursor: Option<Uint64>,
+    pub scanned: Uint64,
+    pub inserted: Uint64,
+    // Contracts whose values conflict with a unique index
+    pub skipped: Uint64,
+    pub is_complete: bool,
+    pub started_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct ManagedIndexingParams {
+    pub limit: Option<u32>,
+    // Start over from the first contract, discarding any saved progress
+    pub restart: Option<bool>,
+}
+
+#[cw_serde]
+pub struct ManagedIndexingProgress {
+    // ID of the last contract scanned
+    pub cursor: Option<Uint64>,
+    pub scanned: Uint64,
+    pub is_complete: bool,
+    pub started_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
 #[cw_serde]
 pub enum GroupSelector {
     WithName(String),
@@ -517,6 +982,12 @@ pub struct TablePartitionsQueryParams {
     pub desc: Option<bool>,
 }
 
+#[cw_serde]
+pub struct AddressPredictionParams {
+    pub code_id: Uint64,
+    pub salt: Binary,
+}
+
 #[cw_serde]
 pub struct TableIndicesQueryParams {
     pub cursor: Option<String>,
@@ -550,12 +1021,65 @@ pub struct PartitionCreationParams {
 pub struct IndexCreationParams {
     pub index_type: IndexType,
     pub name: String,
+    pub constraints: Option<IndexConstraints>,
+    pub unique: Option<UniquenessScope>,
+    pub strings: Option<StringIndexSettings>,
+    // Let contracts hold a set of values, each with its own index entry. A
+    // contract matching a range query on more than one of its values is
+    // returned once for each of them.
+    pub multi: Option<bool>,
+    // Count the contracts with each distinct value, per partition. Intended
+    // for indices with few distinct values.
+    pub facets: Option<bool>,
+    // Index each lower-cased word of a string index's values, for text search.
+    pub text: Option<bool>,
+}
+
+#[cw_serde]
+pub struct IndexDeletionParams {
+    pub name: String,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct CompoundIndexCreationParams {
+    pub name: String,
+    pub components: Vec<String>,
 }
 
 #[cw_serde]
 pub enum IndexQueryParams {
     Equals(String),
     Between(Range),
+    Compound(CompoundRange),
+    // Match string or binary (base64) values beginning with the given prefix
+    StartsWith(String),
+    // Match contracts with any value for the index. Contracts are listed once
+    // each, even for set-valued indices, which are scanned in order of ID.
+    IsSet,
+    // Match contracts without a value for the index
+    IsUnset,
+    // Match the words of a text index
+    Text(TextSearch),
+    // Match locations of a geo index within the given area
+    Geo(GeoArea),
+}
+
+/// Query params for text indices. Contracts match if their value contains all
+/// (or, with `any`, at least one) of the words in the given text.
+#[cw_serde]
+pub struct TextSearch {
+    pub text: String,
+    pub any: Option<bool>,
+}
+
+/// Query params for compound indices: exact values for the leading components,
+/// followed by an optional range over the next component.
+#[cw_serde]
+pub struct CompoundRange {
+    pub equals: Vec<String>,
+    pub start: Option<String>,
+    pub stop: Option<String>,
 }
 
 #[cw_serde]
@@ -567,4 +1091,5 @@ pub struct RangeQueryParams {
     pub limit: Option<u32>,
     pub cursor: Option<Cursor>,
     pub details: Option<Details>,
+    pub exclude_expired: Option<bool>,
 }
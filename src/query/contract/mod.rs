@@ -2,8 +2,10 @@ mod groups;
 mod is_related_to;
 mod relationships;
 mod tags;
+mod values;
 
 pub use groups::query_groups as groups;
 pub use is_related_to::is_related_to;
 pub use relationships::query_relationships as relationships;
 pub use tags::query_tags as tags;
+pub use values::query_values as values;
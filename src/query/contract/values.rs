@@ -0,0 +1,85 @@
+use std::marker::PhantomData;
+
+use crate::error::ContractError;
+use crate::msg::{ContractValuesQueryParams, ContractValuesResponse, IndexValue};
+use crate::state::{
+    load_contract_id, load_index_set_values, load_index_value, CONTRACT_INDEX_TYPES, INDEX_METADATA,
+};
+use crate::util::trim_padding;
+use cosmwasm_std::{Deps, Order};
+use cw_storage_plus::Bound;
+
+pub fn query_values(
+    deps: Deps,
+    params: ContractValuesQueryParams,
+) -> Result<ContractValuesResponse, ContractError> {
+    let contract_id = load_contract_id(deps.storage, &params.contract)?;
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let desc = params.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+    let (min, max) = match order {
+        Order::Ascending => (
+            params
+                .cursor
+                .and_then(|start_key| Some(Bound::Exclusive((start_key, PhantomData)))),
+            None,
+        ),
+        Order::Descending => (
+            None,
+            params
+                .cursor
+                .and_then(|start_key| Some(Bound::Exclusive((start_key, PhantomData)))),
+        ),
+    };
+
+    let mut cursor: Option<String> = None;
+    let mut values: Vec<(String, IndexValue)> = Vec::with_capacity(4);
+    let mut n_keys: usize = 0;
+
+    for result in CONTRACT_INDEX_TYPES
+        .prefix(contract_id)
+        .range(deps.storage, min, max, order)
+        .filter(|result| match result {
+            // Skip values left behind by deleted indices
+            Ok((key, _)) => {
+                params.keys.as_ref().map_or(true, |keys| keys.contains(key))
+                    && INDEX_METADATA.has(deps.storage, key.clone())
+            },
+            Err(_) => true,
+        })
+        .take(limit)
+    {
+        let (key, index_type) = result?;
+        let meta = INDEX_METADATA.load(deps.storage, key.clone())?;
+        // Set-valued indices have a pair for each element
+        let key_values = if meta.multi.unwrap_or(false) {
+            load_index_set_values(deps.storage, contract_id, &key)?
+        } else {
+            vec![load_index_value(
+                deps.storage,
+                contract_id,
+                &key,
+                &index_type,
+            )?]
+        };
+        for value in key_values.into_iter() {
+            let value = match value {
+                IndexValue::String(s) => IndexValue::String(trim_padding(&s)),
+                value => value,
+            };
+            values.push((key.clone(), value));
+        }
+        n_keys += 1;
+        cursor = Some(key);
+    }
+
+    if n_keys < limit {
+        cursor = None;
+    }
+
+    Ok(ContractValuesResponse { values, cursor })
+}
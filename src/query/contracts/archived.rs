@@ -0,0 +1,54 @@
+use std::marker::PhantomData;
+
+use crate::error::ContractError;
+use crate::models::ContractTombstone;
+use crate::msg::{ArchivedContractsResponse, ArchivedQueryParams};
+use crate::state::CONTRACT_TOMBSTONES;
+use cosmwasm_std::{Deps, Order};
+use cw_storage_plus::Bound;
+
+/// Paginate archived contracts by contract ID.
+pub fn archived(
+    deps: Deps,
+    params: ArchivedQueryParams,
+) -> Result<ArchivedContractsResponse, ContractError> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let desc = params.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+    let (min, max) = match order {
+        Order::Ascending => (
+            params
+                .cursor
+                .and_then(|id| Some(Bound::Exclusive((id.u64(), PhantomData)))),
+            None,
+        ),
+        Order::Descending => (
+            None,
+            params
+                .cursor
+                .and_then(|id| Some(Bound::Exclusive((id.u64(), PhantomData)))),
+        ),
+    };
+
+    let mut contracts: Vec<ContractTombstone> = Vec::with_capacity(4);
+
+    for result in CONTRACT_TOMBSTONES
+        .range(deps.storage, min, max, order)
+        .take(limit)
+    {
+        let (_, tombstone) = result?;
+        contracts.push(tombstone);
+    }
+
+    let cursor = if contracts.len() == limit {
+        contracts.last().map(|t| t.meta.id)
+    } else {
+        None
+    };
+
+    Ok(ArchivedContractsResponse { contracts, cursor })
+}
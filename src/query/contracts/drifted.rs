@@ -0,0 +1,37 @@
+use crate::error::ContractError;
+use crate::msg::{ContractDrift, ContractsDriftResponse, DriftQueryParams};
+use crate::state::{detect_contract_drift, select_contract_ids};
+use cosmwasm_std::{Deps, Env};
+
+/// Scan a page of contracts matching the selector, returning those whose
+/// stored code ID or admin disagrees with their on-chain contract info.
+pub fn drifted(
+    deps: Deps,
+    env: Env,
+    params: DriftQueryParams,
+) -> Result<ContractsDriftResponse, ContractError> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let contract_ids = select_contract_ids(
+        deps.storage,
+        &params.select,
+        params.cursor.map(|c| c.u64()),
+        limit,
+    )?;
+
+    let mut contracts: Vec<ContractDrift> = Vec::with_capacity(4);
+
+    for contract_id in contract_ids.iter() {
+        if let Some(drift) = detect_contract_drift(deps, &env.contract.address, *contract_id)? {
+            contracts.push(drift);
+        }
+    }
+
+    // The cursor is the last contract scanned, not the last one returned
+    let cursor = if contract_ids.len() == limit {
+        contract_ids.last().map(|id| (*id).into())
+    } else {
+        None
+    };
+
+    Ok(ContractsDriftResponse { contracts, cursor })
+}
@@ -1,10 +1,14 @@
+mod archived;
 mod by_addresses;
+mod drifted;
 mod in_group;
 mod range;
 mod related_to;
 mod with_tag;
 
+pub use archived::archived;
 pub use by_addresses::by_addresses;
+pub use drifted::drifted;
 pub use in_group::in_group;
 pub use range::range;
 pub use related_to::related_to;
@@ -1,23 +1,35 @@
-use std::marker::PhantomData;
-use std::str::FromStr;
+use std::{collections::BTreeSet, marker::PhantomData};
 
-use crate::msg::{ContractsRangeResponse, Cursor, IndexQueryParams, RangeSelector};
+use crate::msg::{
+    CompoundRange, ContractsRangeResponse, Cursor, GeoArea, IndexQueryParams, IndexType,
+    RangeSelector, TextSearch,
+};
 use crate::state::{
-    load_contract_records, ContractID, CustomIndexMap, PartitionID, IX_CODE_ID, IX_CONTRACT_ID,
-    IX_CREATED_AT, IX_CREATED_BY, IX_REV, IX_UPDATED_AT, IX_UPDATED_BY,
+    is_expired, load_contract_records, load_string_index_settings, CompoundIndexMap, ContractID,
+    CustomIndexMap, PartitionID, TextIndexMap, COMPOUND_INDEX_METADATA, CONFIG_STR_CASE_SENSITIVE,
+    CONTRACT_INDEX_TYPES, INDEX_METADATA, IX_CODE_ID, IX_CONTRACT_ID, IX_CREATED_AT, IX_CREATED_BY,
+    IX_MANAGED, IX_REV, IX_UPDATED_AT, IX_UPDATED_BY, VALUES_COMPOUND, VALUES_GEO,
+};
+use crate::util::{
+    build_index_storage_key, build_text_index_storage_key, encode_ordered, geo_area_contains,
+    geohash_cover, normalize_index_str, pad, parse, parse_bool, parse_index_value,
+    prefix_successor, tokenize_text, validate_geo_area, GEOHASH_PRECISION,
 };
-use crate::util::{build_index_storage_key, pad, parse, parse_bool};
 use crate::{error::ContractError, msg::RangeQueryParams};
-use cosmwasm_std::{Api, Binary, Deps, Order, StdResult, Storage, Uint64};
+use cosmwasm_std::{
+    Api, Binary, Decimal, Decimal256, Deps, Env, Order, StdResult, Storage, Uint256, Uint64,
+};
 use cw_storage_plus::{Bound, KeyDeserialize, Map, Prefixer, PrimaryKey};
 
 pub fn range(
     deps: Deps,
+    env: Env,
     query: RangeQueryParams,
 ) -> Result<ContractsRangeResponse, ContractError> {
     // let limit = query.limit.unwrap_or(20).clamp(1, 200) as usize;
     // let desc = query.desc.unwrap_or(false);
     let details = query.details.clone();
+    let exclude_expired = query.exclude_expired.unwrap_or(false);
 
     // Find matching contract ID's
     let (ids, cursor) = match query.params.clone() {
@@ -32,8 +44,47 @@ pub fn range(
             range.stop,
             false,
         ),
+        IndexQueryParams::Compound(params) => {
+            get_compound_contract_ids(deps.storage, query, params)
+        },
+        IndexQueryParams::StartsWith(prefix) => {
+            get_contract_ids_starting_with(deps.storage, query, prefix)
+        },
+        IndexQueryParams::IsSet => {
+            if matches!(query.index, RangeSelector::Compound(_)) {
+                let params = CompoundRange {
+                    equals: vec![],
+                    start: None,
+                    stop: None,
+                };
+                get_compound_contract_ids(deps.storage, query, params)
+            } else if is_multi_index(deps.storage, &query.index)? {
+                // Set-valued indices have an entry per element, so scan the
+                // partition's contracts instead to list each contract once
+                scan_contract_ids(deps.storage, query, true)
+            } else {
+                get_contract_ids(deps.api, deps.storage, query, None, None, false)
+            }
+        },
+        IndexQueryParams::IsUnset => scan_contract_ids(deps.storage, query, false),
+        IndexQueryParams::Text(search) => get_text_contract_ids(deps.storage, query, search),
+        IndexQueryParams::Geo(area) => get_geo_contract_ids(deps.storage, query, area),
     }?;
 
+    // Filter out expired contracts. The cursor still points to the end of the
+    // page, so a page may contain fewer contracts than the limit.
+    let ids = if exclude_expired {
+        let mut unexpired_ids = Vec::with_capacity(ids.len());
+        for id in ids.into_iter() {
+            if !is_expired(deps.storage, &env.block, id)? {
+                unexpired_ids.push(id);
+            }
+        }
+        unexpired_ids
+    } else {
+        ids
+    };
+
     // Convert contract ID's to Addrs
     let contracts = load_contract_records(deps.storage, &ids, details)?;
 
@@ -46,6 +97,7 @@ fn build_bounds<'a, T>(
     range_start_value: T,
     range_stop_value: T,
     maybe_cursor: Option<Cursor>,
+    fn_parse: &dyn Fn(String) -> Result<T, ContractError>,
 ) -> Result<
     (
         Option<Bound<'a, (PartitionID, T, u64)>>,
@@ -54,14 +106,14 @@ fn build_bounds<'a, T>(
     ContractError,
 >
 where
-    T: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize + FromStr,
+    T: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
 {
     Ok(match order {
         Order::Ascending => {
             (
                 // min
                 Some(if let Some((p, v_str, id)) = maybe_cursor {
-                    let v = parse(v_str)?;
+                    let v = fn_parse(v_str)?;
                     Bound::Exclusive(((p, v, id.u64()), PhantomData))
                 } else {
                     Bound::Inclusive(((partition, range_start_value, u64::MIN), PhantomData))
@@ -82,7 +134,7 @@ where
                 ))),
                 // max
                 Some(if let Some((p, v_str, id)) = maybe_cursor {
-                    let v = parse(v_str)?;
+                    let v = fn_parse(v_str)?;
                     Bound::Exclusive(((p, v, id.u64()), PhantomData))
                 } else {
                     Bound::Inclusive(((partition, range_stop_value, u64::MAX), PhantomData))
@@ -114,13 +166,13 @@ fn build_range_bounds_str<'a>(
                 } else if let Some(v) = range_start_value {
                     Some(Bound::Inclusive(((partition, v, u64::MIN), PhantomData)))
                 } else {
-                    None
+                    Some(Bound::InclusiveRaw(partition_start_key(partition)))
                 },
                 // max
                 if let Some(v) = range_stop_value {
                     Some(Bound::Inclusive(((partition, v, u64::MAX), PhantomData)))
                 } else {
-                    None
+                    partition_end_key(partition).map(Bound::ExclusiveRaw)
                 },
             )
         },
@@ -130,7 +182,7 @@ fn build_range_bounds_str<'a>(
                 if let Some(v) = range_start_value {
                     Some(Bound::Exclusive(((partition, v, u64::MIN), PhantomData)))
                 } else {
-                    None
+                    Some(Bound::InclusiveRaw(partition_start_key(partition)))
                 },
                 // max
                 if let Some((p, v_str, id)) = maybe_cursor {
@@ -138,14 +190,30 @@ fn build_range_bounds_str<'a>(
                 } else if let Some(v) = range_stop_value {
                     Some(Bound::Inclusive(((partition, v, u64::MAX), PhantomData)))
                 } else {
-                    None
+                    partition_end_key(partition).map(Bound::ExclusiveRaw)
                 },
             )
         },
     })
 }
 
-fn build_start_stop<T: FromStr + Clone>(
+/// Raw key at which a partition's entries begin in an index keyed by
+/// partition first, for ranges over values without a natural minimum, such
+/// as strings. Key parts other than the last are prefixed by their length.
+fn partition_start_key(partition: PartitionID) -> Vec<u8> {
+    let mut key = (std::mem::size_of::<PartitionID>() as u16)
+        .to_be_bytes()
+        .to_vec();
+    key.extend(partition.to_be_bytes());
+    key
+}
+
+/// Raw key just past a partition's entries, unless it's the last partition.
+fn partition_end_key(partition: PartitionID) -> Option<Vec<u8>> {
+    partition.checked_add(1).map(partition_start_key)
+}
+
+fn build_start_stop<T: Clone>(
     start_value_raw: Option<String>,
     start_value_default: T,
     stop_value_raw: Option<String>,
@@ -164,7 +232,7 @@ fn build_start_stop<T: FromStr + Clone>(
     }
 
     let stop = if let Some(raw_value) = stop_value_raw {
-        parse::<T>(raw_value)?
+        fn_parse(raw_value)?
     } else {
         stop_value_default
     };
@@ -260,7 +328,7 @@ fn get_contract_ids(
             let index = IX_CONTRACT_ID;
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -269,7 +337,7 @@ fn get_contract_ids(
             let index = IX_CODE_ID;
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -278,7 +346,7 @@ fn get_contract_ids(
             let index = IX_REV;
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -287,7 +355,7 @@ fn get_contract_ids(
             let index = IX_CREATED_AT;
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -296,7 +364,16 @@ fn get_contract_ids(
             let index = IX_UPDATED_AT;
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
+            page(index.keys(store, min, max, order), limit, &|x| {
+                x.to_string()
+            })?
+        },
+        RangeSelector::IsManaged => {
+            let index = IX_MANAGED;
+            let (start, stop) =
+                build_start_stop(raw_start, u8::MIN, raw_stop, u8::MAX, exact, &parse_bool)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -320,7 +397,17 @@ fn get_contract_ids(
         RangeSelector::String(index_name) => {
             let storage_key = build_index_storage_key(index_name);
             let index: CustomIndexMap<String> = Map::new(&storage_key);
-            let (start, stop) = build_start_stop_values_str(raw_start, raw_stop, exact, None)?;
+            let settings = load_string_index_settings(store, index_name)?;
+            let normalize = |raw: Option<String>| -> Result<Option<String>, ContractError> {
+                raw.map(|s| normalize_index_str(index_name, &settings, &s, true))
+                    .transpose()
+            };
+            let (start, stop) = build_start_stop_values_str(
+                normalize(raw_start)?,
+                normalize(raw_stop)?,
+                exact,
+                None,
+            )?;
             let (min, max) = build_range_bounds_str(order, partition, start, stop, query.cursor)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
@@ -331,7 +418,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u8> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u8::MIN, raw_stop, u8::MAX, exact, &parse_bool)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -341,7 +428,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u64> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -351,7 +438,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<i32> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, i32::MIN, raw_stop, i32::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -361,7 +448,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u8> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u8::MIN, raw_stop, u8::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -371,7 +458,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u16> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u16::MIN, raw_stop, u16::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -381,7 +468,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u32> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u32::MIN, raw_stop, u32::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -391,7 +478,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u64> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u64::MIN, raw_stop, u64::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -401,7 +488,7 @@ fn get_contract_ids(
             let index: CustomIndexMap<u128> = Map::new(&storage_key);
             let (start, stop) =
                 build_start_stop(raw_start, u128::MIN, raw_stop, u128::MAX, exact, &parse)?;
-            let (min, max) = build_bounds(order, partition, start, stop, query.cursor)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
             page(index.keys(store, min, max, order), limit, &|x| {
                 x.to_string()
             })?
@@ -434,7 +521,7 @@ fn get_contract_ids(
                                 PhantomData,
                             )))
                         } else {
-                            None
+                            Some(Bound::InclusiveRaw(partition_start_key(partition)))
                         },
                         // max
                         if let Some(v) = stop {
@@ -444,7 +531,7 @@ fn get_contract_ids(
                                 PhantomData,
                             )))
                         } else {
-                            None
+                            partition_end_key(partition).map(Bound::ExclusiveRaw)
                         },
                     )
                 },
@@ -458,7 +545,7 @@ fn get_contract_ids(
                                 PhantomData,
                             )))
                         } else {
-                            None
+                            Some(Bound::InclusiveRaw(partition_start_key(partition)))
                         },
                         // max
                         if let Some((p, v_str, id)) = query.cursor {
@@ -474,7 +561,7 @@ fn get_contract_ids(
                                 PhantomData,
                             )))
                         } else {
-                            None
+                            partition_end_key(partition).map(Bound::ExclusiveRaw)
                         },
                     )
                 },
@@ -494,5 +581,748 @@ fn get_contract_ids(
                 Binary::from(x.as_slice()).to_base64()
             })?
         },
+        RangeSelector::Int64(index_name) => {
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<i64> = Map::new(&storage_key);
+            let (start, stop) =
+                build_start_stop(raw_start, i64::MIN, raw_stop, i64::MAX, exact, &parse)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
+            page(index.keys(store, min, max, order), limit, &|x| {
+                x.to_string()
+            })?
+        },
+        RangeSelector::Int128(index_name) => {
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<i128> = Map::new(&storage_key);
+            let (start, stop) =
+                build_start_stop(raw_start, i128::MIN, raw_stop, i128::MAX, exact, &parse)?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
+            page(index.keys(store, min, max, order), limit, &|x| {
+                x.to_string()
+            })?
+        },
+        RangeSelector::Decimal(index_name) => {
+            // Keyed by the decimal's atomics, which are also used in cursors
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<u128> = Map::new(&storage_key);
+            let parse_decimal = |s: String| -> Result<u128, ContractError> {
+                Ok(parse::<Decimal>(s)?.atomics().u128())
+            };
+            let (start, stop) = build_start_stop(
+                raw_start,
+                u128::MIN,
+                raw_stop,
+                u128::MAX,
+                exact,
+                &parse_decimal,
+            )?;
+            let (min, max) = build_bounds(order, partition, start, stop, query.cursor, &parse)?;
+            page(index.keys(store, min, max, order), limit, &|x| {
+                x.to_string()
+            })?
+        },
+        RangeSelector::Decimal256(index_name) => {
+            // Keyed by the big-endian bytes of the decimal's atomics
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<Vec<u8>> = Map::new(&storage_key);
+            let parse_decimal = |s: String| -> Result<Vec<u8>, ContractError> {
+                Ok(parse::<Decimal256>(s)?.atomics().to_be_bytes().to_vec())
+            };
+            let (start, stop) = build_start_stop(
+                raw_start,
+                vec![u8::MIN; 32],
+                raw_stop,
+                vec![u8::MAX; 32],
+                exact,
+                &parse_decimal,
+            )?;
+            let (min, max) =
+                build_bounds(order, partition, start, stop, query.cursor, &parse_decimal)?;
+            page(
+                index.keys(store, min, max, order),
+                limit,
+                &|x| match <[u8; 32]>::try_from(x.as_slice()) {
+                    Ok(bytes) => Decimal256::new(Uint256::from_be_bytes(bytes)).to_string(),
+                    Err(_) => String::new(),
+                },
+            )?
+        },
+        RangeSelector::Addr(index_name) => {
+            // Addr keys are stored as their string bytes
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<String> = Map::new(&storage_key);
+            let (start, stop) = build_start_stop_values_str(raw_start, raw_stop, exact, None)?;
+            let (min, max) = build_range_bounds_str(order, partition, start, stop, query.cursor)?;
+            page(index.keys(store, min, max, order), limit, &|x| {
+                x.to_string()
+            })?
+        },
+        RangeSelector::Geo(index_name) => {
+            // Geo keys are the geohash of each point
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<String> = Map::new(&storage_key);
+            let (start, stop) = build_start_stop_values_str(raw_start, raw_stop, exact, None)?;
+            let (min, max) = build_range_bounds_str(order, partition, start, stop, query.cursor)?;
+            page(index.keys(store, min, max, order), limit, &|x| {
+                x.to_string()
+            })?
+        },
+        RangeSelector::Compound(index_name) => {
+            return Err(ContractError::ValidationError {
+                reason: format!(
+                    "compound index {} requires compound query params",
+                    index_name
+                ),
+            })
+        },
+    })
+}
+
+/// Name of the custom index selected, if any.
+fn custom_index_name(selector: &RangeSelector) -> Option<&String> {
+    match selector {
+        RangeSelector::String(index_name)
+        | RangeSelector::Bool(index_name)
+        | RangeSelector::Timestamp(index_name)
+        | RangeSelector::Int32(index_name)
+        | RangeSelector::Uint8(index_name)
+        | RangeSelector::Uint16(index_name)
+        | RangeSelector::Uint32(index_name)
+        | RangeSelector::Uint64(index_name)
+        | RangeSelector::Uint128(index_name)
+        | RangeSelector::Binary(index_name)
+        | RangeSelector::Int64(index_name)
+        | RangeSelector::Int128(index_name)
+        | RangeSelector::Decimal(index_name)
+        | RangeSelector::Decimal256(index_name)
+        | RangeSelector::Addr(index_name)
+        | RangeSelector::Geo(index_name) => Some(index_name),
+        _ => None,
+    }
+}
+
+/// Return true if the selected index is a set-valued custom index.
+fn is_multi_index(
+    store: &dyn Storage,
+    selector: &RangeSelector,
+) -> Result<bool, ContractError> {
+    Ok(match custom_index_name(selector) {
+        Some(index_name) => INDEX_METADATA
+            .may_load(store, index_name.clone())?
+            .map_or(false, |meta| meta.multi.unwrap_or(false)),
+        None => false,
     })
 }
+
+/// Max number of contracts scanned per page of an IsUnset query, or of an
+/// IsSet query of a set-valued index
+const MAX_UNSET_SCAN: usize = 1000;
+
+/// Page through the contracts in a partition with, or without, a value for a
+/// custom or compound index, by scanning the partition's contract ID index.
+/// At most MAX_UNSET_SCAN contracts are scanned per page, so a page may
+/// contain fewer contracts than the limit, even when more remain.
+fn scan_contract_ids(
+    store: &dyn Storage,
+    query: RangeQueryParams,
+    is_set: bool,
+) -> Result<(Vec<u64>, Option<Cursor>), ContractError> {
+    let (index_name, is_compound) = match (&query.index, custom_index_name(&query.index)) {
+        (RangeSelector::Compound(index_name), _) => (index_name, true),
+        (_, Some(index_name)) => (index_name, false),
+        _ => {
+            return Err(ContractError::ValidationError {
+                reason: "IsUnset only applies to custom indices".to_owned(),
+            })
+        },
+    };
+
+    let exists = if is_compound {
+        COMPOUND_INDEX_METADATA.has(store, index_name.clone())
+    } else {
+        INDEX_METADATA.has(store, index_name.clone())
+    };
+    if !exists {
+        return Err(ContractError::UnknownIndex {
+            index: index_name.clone(),
+        });
+    }
+
+    let partition = query.partition;
+    let limit = query.limit.unwrap_or(20).clamp(1, 200) as usize;
+    let desc = query.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+
+    let (min, max) = build_bounds(order, partition, u64::MIN, u64::MAX, query.cursor, &parse)?;
+
+    let mut contract_ids: Vec<ContractID> = Vec::with_capacity(limit);
+    let mut cursor: Option<Cursor> = None;
+
+    for result in IX_CONTRACT_ID
+        .keys(store, min, max, order)
+        .take(MAX_UNSET_SCAN)
+    {
+        let (p, id, contract_id) = result?;
+        cursor = Some((p, id.to_string(), contract_id.into()));
+        let has_value = if is_compound {
+            VALUES_COMPOUND.has(store, (contract_id, index_name))
+        } else {
+            CONTRACT_INDEX_TYPES.has(store, (contract_id, index_name))
+        };
+        if has_value == is_set {
+            contract_ids.push(contract_id);
+            if contract_ids.len() == limit {
+                break;
+            }
+        }
+    }
+
+    Ok((contract_ids, cursor))
+}
+
+/// Max number of words in the text of a Text query
+const MAX_TEXT_QUERY_WORDS: usize = 10;
+
+/// Max number of contracts scanned per page of a Text query matching all words
+const MAX_TEXT_SCAN: usize = 1000;
+
+/// Page through the contracts in a partition whose values of a text index
+/// contain all or any of the words in the given text. When matching all words,
+/// contracts with the first word are scanned and checked for the others. At
+/// most MAX_TEXT_SCAN contracts are scanned per page, so a page may contain
+/// fewer contracts than the limit, even when more remain.
+fn get_text_contract_ids(
+    store: &dyn Storage,
+    query: RangeQueryParams,
+    search: TextSearch,
+) -> Result<(Vec<u64>, Option<Cursor>), ContractError> {
+    let index_name = match &query.index {
+        RangeSelector::String(index_name) => index_name,
+        _ => {
+            return Err(ContractError::ValidationError {
+                reason: "Text only applies to string indices".to_owned(),
+            })
+        },
+    };
+
+    let meta = INDEX_METADATA
+        .may_load(store, index_name.clone())?
+        .ok_or_else(|| ContractError::UnknownIndex {
+            index: index_name.clone(),
+        })?;
+    if !meta.text.unwrap_or(false) {
+        return Err(ContractError::ValidationError {
+            reason: format!("index {} does not support text search", index_name),
+        });
+    }
+
+    let words: Vec<String> = tokenize_text(&search.text).into_iter().collect();
+    if words.is_empty() || words.len() > MAX_TEXT_QUERY_WORDS {
+        return Err(ContractError::ValidationError {
+            reason: format!(
+                "text search must contain between 1 and {} words",
+                MAX_TEXT_QUERY_WORDS
+            ),
+        });
+    }
+
+    let partition = query.partition;
+    let limit = query.limit.unwrap_or(20).clamp(1, 200) as usize;
+    let desc = query.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+
+    let start = query.cursor.map(|(_, _, id)| Bound::exclusive(id.u64()));
+    let (min, max) = match order {
+        Order::Ascending => (start, None),
+        Order::Descending => (None, start),
+    };
+
+    let storage_key = build_text_index_storage_key(index_name);
+    let index: TextIndexMap = Map::new(&storage_key);
+    let to_cursor = |id: ContractID| (partition, String::new(), id.into());
+
+    if search.any.unwrap_or(false) {
+        // The first page of the union of the words' contract IDs lies within
+        // the union of the first page of each.
+        let mut contract_ids: BTreeSet<ContractID> = BTreeSet::new();
+        for word in words.iter() {
+            for result in index
+                .prefix((partition, word.as_str()))
+                .keys(store, min.clone(), max.clone(), order)
+                .take(limit)
+            {
+                contract_ids.insert(result?);
+            }
+        }
+        let contract_ids: Vec<ContractID> = if desc {
+            contract_ids.into_iter().rev().take(limit).collect()
+        } else {
+            contract_ids.into_iter().take(limit).collect()
+        };
+        let cursor = if contract_ids.len() == limit {
+            contract_ids.last().copied().map(to_cursor)
+        } else {
+            None
+        };
+        return Ok((contract_ids, cursor));
+    }
+
+    let mut contract_ids: Vec<ContractID> = Vec::with_capacity(limit);
+    let mut cursor: Option<Cursor> = None;
+    let mut n_scanned: usize = 0;
+
+    for result in index
+        .prefix((partition, words[0].as_str()))
+        .keys(store, min, max, order)
+    {
+        let contract_id = result?;
+        n_scanned += 1;
+        if words[1..]
+            .iter()
+            .all(|word| index.has(store, (partition, word.as_str(), contract_id)))
+        {
+            contract_ids.push(contract_id);
+        }
+        if contract_ids.len() == limit || n_scanned == MAX_TEXT_SCAN {
+            cursor = Some(to_cursor(contract_id));
+            break;
+        }
+    }
+
+    Ok((contract_ids, cursor))
+}
+
+/// Max number of geohash cells covering the area of a Geo query
+const MAX_GEO_CELLS: u64 = 32;
+
+/// Max number of contracts scanned per page of a Geo query
+const MAX_GEO_SCAN: usize = 1000;
+
+/// Page through the contracts in a partition whose locations in a geo index
+/// lie within the given area. The area is covered by geohash cells, whose
+/// entries are scanned in order and filtered by their exact location. At most
+/// MAX_GEO_SCAN contracts are scanned per page, so a page may contain fewer
+/// contracts than the limit, even when more remain.
+fn get_geo_contract_ids(
+    store: &dyn Storage,
+    query: RangeQueryParams,
+    area: GeoArea,
+) -> Result<(Vec<u64>, Option<Cursor>), ContractError> {
+    let index_name = match &query.index {
+        RangeSelector::Geo(index_name) => index_name,
+        _ => {
+            return Err(ContractError::ValidationError {
+                reason: "Geo only applies to geo indices".to_owned(),
+            })
+        },
+    };
+    if !INDEX_METADATA.has(store, index_name.clone()) {
+        return Err(ContractError::UnknownIndex {
+            index: index_name.clone(),
+        });
+    }
+    validate_geo_area(&area)?;
+
+    let partition = query.partition;
+    let limit = query.limit.unwrap_or(20).clamp(1, 200) as usize;
+    let desc = query.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+
+    let mut cells = geohash_cover(&area, MAX_GEO_CELLS);
+    if desc {
+        cells.reverse();
+    }
+
+    // Every stored geohash has the same length, so the entries of each cell
+    // lie between its prefix padded with the first and last geohash digits.
+    let pad_hash = |cell: &String, c: char| -> String {
+        let mut hash = cell.clone();
+        hash.extend(std::iter::repeat(c).take(GEOHASH_PRECISION - cell.len()));
+        hash
+    };
+
+    let storage_key = build_index_storage_key(index_name);
+    let index: CustomIndexMap<String> = Map::new(&storage_key);
+    let mut contract_ids: Vec<ContractID> = Vec::with_capacity(limit);
+    let mut n_scanned: usize = 0;
+
+    for cell in cells.iter() {
+        let (first, last) = (pad_hash(cell, '0'), pad_hash(cell, 'z'));
+
+        // Skip cells already paged through, resuming within the cursor's cell
+        let maybe_cursor = match (&query.cursor, order) {
+            (Some((_, hash, _)), Order::Ascending) if *hash > last => continue,
+            (Some((_, hash, _)), Order::Descending) if *hash < first => continue,
+            (Some(cursor), Order::Ascending) if cursor.1 >= first => Some(cursor.clone()),
+            (Some(cursor), Order::Descending) if cursor.1 <= last => Some(cursor.clone()),
+            _ => None,
+        };
+
+        let (min, max) =
+            build_range_bounds_str(order, partition, Some(first), Some(last), maybe_cursor)?;
+
+        for result in index.keys(store, min, max, order) {
+            let (p, hash, contract_id) = result?;
+            n_scanned += 1;
+            let point = VALUES_GEO.load(store, (contract_id, index_name))?;
+            if geo_area_contains(&area, &point) {
+                contract_ids.push(contract_id);
+            }
+            if contract_ids.len() == limit || n_scanned == MAX_GEO_SCAN {
+                return Ok((contract_ids, Some((p, hash, contract_id.into()))));
+            }
+        }
+    }
+
+    Ok((contract_ids, None))
+}
+
+/// Page through a string, binary or geo index, matching values that begin with
+/// the given prefix. String prefixes are normalized like the values of the
+/// index they're matched against, though without padding. Geo prefixes are
+/// geohashes.
+fn get_contract_ids_starting_with(
+    store: &dyn Storage,
+    query: RangeQueryParams,
+    prefix: String,
+) -> Result<(Vec<u64>, Option<Cursor>), ContractError> {
+    let partition = query.partition;
+    let limit = query.limit.unwrap_or(20).clamp(1, 200) as usize;
+    let desc = query.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+
+    let str_prefix = if CONFIG_STR_CASE_SENSITIVE.load(store)? {
+        prefix.clone()
+    } else {
+        prefix.to_lowercase()
+    };
+    let str_cursor = query
+        .cursor
+        .clone()
+        .map(|(_, v_str, id)| (v_str.into_bytes(), id.u64()));
+    let to_str = |v: &[u8]| String::from_utf8_lossy(v).into_owned();
+
+    match &query.index {
+        RangeSelector::CreatedBy => page_starts_with(
+            store,
+            IX_CREATED_BY,
+            partition,
+            str_prefix.as_bytes(),
+            order,
+            str_cursor,
+            limit,
+            &to_str,
+        ),
+        RangeSelector::UpdatedBy => page_starts_with(
+            store,
+            IX_UPDATED_BY,
+            partition,
+            str_prefix.as_bytes(),
+            order,
+            str_cursor,
+            limit,
+            &to_str,
+        ),
+        RangeSelector::String(index_name) => {
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<String> = Map::new(&storage_key);
+            let settings = load_string_index_settings(store, index_name)?;
+            let index_prefix = normalize_index_str(index_name, &settings, &prefix, false)?;
+            page_starts_with(
+                store,
+                index,
+                partition,
+                index_prefix.as_bytes(),
+                order,
+                str_cursor,
+                limit,
+                &to_str,
+            )
+        },
+        RangeSelector::Geo(index_name) => {
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<String> = Map::new(&storage_key);
+            page_starts_with(
+                store,
+                index,
+                partition,
+                prefix.as_bytes(),
+                order,
+                str_cursor,
+                limit,
+                &to_str,
+            )
+        },
+        RangeSelector::Binary(index_name) => {
+            let storage_key = build_index_storage_key(index_name);
+            let index: CustomIndexMap<Vec<u8>> = Map::new(&storage_key);
+            let bytes_cursor = match query.cursor {
+                Some((_, v_str, id)) => Some((Binary::from_base64(&v_str)?.to_vec(), id.u64())),
+                None => None,
+            };
+            page_starts_with(
+                store,
+                index,
+                partition,
+                Binary::from_base64(&prefix)?.as_slice(),
+                order,
+                bytes_cursor,
+                limit,
+                &|v| Binary::from(v).to_base64(),
+            )
+        },
+        _ => Err(ContractError::ValidationError {
+            reason: "StartsWith only applies to string, binary and geo indices".to_owned(),
+        }),
+    }
+}
+
+/// Page through index entries whose values begin with the given prefix. Values
+/// are length-prefixed within keys, so entries are ordered by length before
+/// content, and the matching entries of each length form a contiguous run.
+/// Rather than visiting every entry, the scan jumps from one run to the next.
+#[allow(clippy::too_many_arguments)]
+fn page_starts_with<'a, D>(
+    store: &dyn Storage,
+    index: Map<'a, (PartitionID, D, ContractID), u8>,
+    partition: PartitionID,
+    prefix: &[u8],
+    order: Order,
+    maybe_cursor: Option<(Vec<u8>, ContractID)>,
+    limit: usize,
+    to_string: &dyn Fn(&[u8]) -> String,
+) -> Result<(Vec<ContractID>, Option<Cursor>), ContractError>
+where
+    D: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    D::Output: AsRef<[u8]>,
+{
+    let n_prefix = prefix.len();
+
+    // Raw key of a (value, contract ID) entry within the partition
+    let raw_key = |value: &[u8], contract_id: ContractID| -> Vec<u8> {
+        let mut key = (value.len() as u16).to_be_bytes().to_vec();
+        key.extend_from_slice(value);
+        key.extend(contract_id.to_be_bytes());
+        key
+    };
+    let pad_bytes = |bytes: &[u8], n: usize| -> Vec<u8> {
+        let mut padded = bytes.to_vec();
+        padded.resize(n, 0);
+        padded
+    };
+
+    let mut bound: Option<Bound<(D, ContractID)>> = match (maybe_cursor, order) {
+        (Some((value, contract_id)), _) => Some(Bound::ExclusiveRaw(raw_key(&value, contract_id))),
+        (None, Order::Ascending) => Some(Bound::InclusiveRaw(raw_key(prefix, 0))),
+        (None, Order::Descending) => None,
+    };
+
+    let entries = index.sub_prefix(partition);
+    let mut contract_ids: Vec<ContractID> = Vec::with_capacity(limit);
+    let mut cursor: Option<Cursor> = None;
+
+    'scan: while contract_ids.len() < limit {
+        let (min, max) = match order {
+            Order::Ascending => (bound.take(), None),
+            Order::Descending => (None, bound.take()),
+        };
+
+        let mut next_bound: Option<Bound<(D, ContractID)>> = None;
+
+        for result in entries.keys(store, min, max, order) {
+            let (value, contract_id) = result?;
+            let value: &[u8] = value.as_ref();
+
+            if value.starts_with(prefix) {
+                cursor = Some((partition, to_string(value), contract_id.into()));
+                contract_ids.push(contract_id);
+                if contract_ids.len() == limit {
+                    break 'scan;
+                }
+                continue;
+            }
+
+            // Jump to the next run of entries that may match, if any
+            let n = value.len();
+            next_bound = match order {
+                Order::Ascending => Some(Bound::InclusiveRaw(if n < n_prefix {
+                    raw_key(prefix, 0)
+                } else if &value[..n_prefix] < prefix {
+                    raw_key(&pad_bytes(prefix, n), 0)
+                } else {
+                    raw_key(&pad_bytes(prefix, n + 1), 0)
+                })),
+                Order::Descending => {
+                    if n < n_prefix {
+                        None
+                    } else if &value[..n_prefix] > prefix {
+                        prefix_successor(prefix)
+                            .map(|succ| Bound::ExclusiveRaw(raw_key(&pad_bytes(&succ, n), 0)))
+                    } else if n > n_prefix {
+                        Some(Bound::ExclusiveRaw(raw_key(&vec![0; n], 0)))
+                    } else {
+                        None
+                    }
+                },
+            };
+            break;
+        }
+
+        match next_bound {
+            Some(b) => bound = Some(b),
+            None => break,
+        }
+    }
+
+    Ok((contract_ids, cursor))
+}
+
+/// Page through a compound index, matching exact values for the leading
+/// components and an optional range over the next one. The cursor value is the
+/// base64-encoded key of the last entry returned.
+fn get_compound_contract_ids(
+    store: &dyn Storage,
+    query: RangeQueryParams,
+    params: CompoundRange,
+) -> Result<(Vec<u64>, Option<Cursor>), ContractError> {
+    let index_name = match &query.index {
+        RangeSelector::Compound(index_name) => index_name,
+        _ => {
+            return Err(ContractError::ValidationError {
+                reason: "compound query params require a compound index".to_owned(),
+            })
+        },
+    };
+
+    let meta = COMPOUND_INDEX_METADATA
+        .may_load(store, index_name.clone())?
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: format!("compound index {} does not exist", index_name),
+        })?;
+    if meta.deletion.is_some() {
+        return Err(ContractError::IndexDeleting {
+            index: index_name.clone(),
+        });
+    }
+
+    let n_equals = params.equals.len();
+    let is_range = params.start.is_some() || params.stop.is_some();
+    if n_equals > meta.components.len() || (n_equals == meta.components.len() && is_range) {
+        return Err(ContractError::ValidationError {
+            reason: format!(
+                "compound index {} has {} components",
+                index_name,
+                meta.components.len()
+            ),
+        });
+    }
+
+    let partition = query.partition;
+    let limit = query.limit.unwrap_or(20).clamp(1, 200) as usize;
+    let desc = query.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+
+    let encode = |component: &String, raw: String| -> Result<Vec<u8>, ContractError> {
+        let index_type = INDEX_METADATA.load(store, component.clone())?.index_type;
+        let raw = if index_type == IndexType::String {
+            let settings = load_string_index_settings(store, component)?;
+            normalize_index_str(component, &settings, &raw, true)?
+        } else {
+            raw
+        };
+        Ok(encode_ordered(&parse_index_value(&index_type, raw)?))
+    };
+
+    // Encode exact values for the leading components as a common key prefix
+    let mut prefix: Vec<u8> = vec![];
+    for (component, raw) in meta.components.iter().zip(params.equals.into_iter()) {
+        prefix.extend(encode(component, raw)?);
+    }
+
+    // Extend the prefix with the start and stop values of the next component
+    let (mut start, mut stop) = (prefix.clone(), prefix_successor(&prefix));
+    if let Some(component) = meta.components.get(n_equals) {
+        if let Some(raw) = params.start {
+            start.extend(encode(component, raw)?);
+        }
+        if let Some(raw) = params.stop {
+            let mut stop_prefix = prefix.clone();
+            stop_prefix.extend(encode(component, raw)?);
+            stop = prefix_successor(&stop_prefix);
+        }
+    }
+
+    let maybe_cursor_key = match query.cursor {
+        Some((_, v_str, _)) => Some(Binary::from_base64(&v_str)?.to_vec()),
+        None => None,
+    };
+
+    let (min, max) = match order {
+        Order::Ascending => (
+            Some(if let Some(key) = maybe_cursor_key {
+                Bound::Exclusive((key, PhantomData))
+            } else {
+                Bound::Inclusive((start, PhantomData))
+            }),
+            stop.map(|key| Bound::Exclusive((key, PhantomData))),
+        ),
+        Order::Descending => (
+            Some(Bound::Inclusive((start, PhantomData))),
+            if let Some(key) = maybe_cursor_key {
+                Some(Bound::Exclusive((key, PhantomData)))
+            } else {
+                stop.map(|key| Bound::Exclusive((key, PhantomData)))
+            },
+        ),
+    };
+
+    let storage_key = build_index_storage_key(index_name);
+    let index: CompoundIndexMap = Map::new(&storage_key);
+
+    let mut contract_ids = Vec::with_capacity(limit);
+    let mut cursor: Option<Cursor> = None;
+
+    for result in index
+        .prefix(partition)
+        .keys(store, min, max, order)
+        .take(limit)
+    {
+        let key = result?;
+        let contract_id = match key.len().checked_sub(8) {
+            Some(i) => u64::from_be_bytes(key[i..].try_into().unwrap()),
+            None => {
+                return Err(ContractError::UnexpectedError {
+                    reason: format!("invalid key in compound index {}", index_name),
+                })
+            },
+        };
+        cursor = Some((
+            partition,
+            Binary::from(key).to_base64(),
+            Uint64::from(contract_id),
+        ));
+        contract_ids.push(contract_id);
+    }
+
+    Ok((contract_ids, cursor))
+}
@@ -0,0 +1,189 @@
+use crate::error::ContractError;
+use crate::msg::{
+    AggregateScope, IndexAggregatesQueryParams, IndexAggregatesResponse, IndexType, IndexValue,
+};
+use crate::state::{
+    CustomIndexMap, PartitionID, GROUP_INDEX_AGGREGATES, GROUP_INDEX_VALUES, INDEX_METADATA,
+    PARTITION_INDEX_AGGREGATES,
+};
+use crate::util::{build_index_storage_key, is_numeric_index_type};
+use cosmwasm_std::{
+    Decimal, Decimal256, Deps, Order, StdResult, Storage, Uint128, Uint256, Uint64,
+};
+use cw_storage_plus::{KeyDeserialize, Map, Prefixer, PrimaryKey};
+
+/// Return the count, sum, average, min and max of a numeric custom index's
+/// values within a partition or group.
+pub fn query_aggregates(
+    deps: Deps,
+    params: IndexAggregatesQueryParams,
+) -> Result<IndexAggregatesResponse, ContractError> {
+    let store = deps.storage;
+    let index_name = &params.index;
+    let meta = INDEX_METADATA
+        .may_load(store, index_name.clone())?
+        .ok_or_else(|| ContractError::UnknownIndex {
+            index: index_name.clone(),
+        })?;
+
+    if !is_numeric_index_type(&meta.index_type) {
+        return Err(ContractError::ValidationError {
+            reason: format!("index {} is not numeric", index_name),
+        });
+    }
+
+    let (aggregate, min, max) = match params.scope {
+        AggregateScope::Partition(partition) => (
+            PARTITION_INDEX_AGGREGATES
+                .may_load(store, (index_name, partition))?
+                .unwrap_or_default(),
+            load_partition_extreme(
+                store,
+                index_name,
+                &meta.index_type,
+                partition,
+                Order::Ascending,
+            )?,
+            load_partition_extreme(
+                store,
+                index_name,
+                &meta.index_type,
+                partition,
+                Order::Descending,
+            )?,
+        ),
+        AggregateScope::Group(group_id) => {
+            let load_extreme = |order: Order| -> StdResult<Option<IndexValue>> {
+                GROUP_INDEX_VALUES
+                    .prefix((index_name, group_id))
+                    .range(store, None, None, order)
+                    .next()
+                    .map(|result| result.map(|(_, value)| value))
+                    .transpose()
+            };
+            (
+                GROUP_INDEX_AGGREGATES
+                    .may_load(store, (index_name, group_id))?
+                    .unwrap_or_default(),
+                load_extreme(Order::Ascending)?,
+                load_extreme(Order::Descending)?,
+            )
+        },
+    };
+
+    // Net the positive and negative sums into a sign and magnitude
+    let (is_negative, magnitude) = if aggregate.sum_positive >= aggregate.sum_negative {
+        (false, aggregate.sum_positive - aggregate.sum_negative)
+    } else {
+        (true, aggregate.sum_negative - aggregate.sum_positive)
+    };
+
+    // Sums and averages of decimal indices are computed in atomics
+    let is_decimal = matches!(meta.index_type, IndexType::Decimal | IndexType::Decimal256);
+    let sign = if is_negative { "-" } else { "" };
+
+    let sum = if is_decimal {
+        format!("{}{}", sign, Decimal256::new(magnitude))
+    } else {
+        format!("{}{}", sign, magnitude)
+    };
+
+    let average = if aggregate.count.is_zero() {
+        None
+    } else {
+        let average = if is_decimal {
+            Decimal256::new(magnitude / Uint256::from(aggregate.count))
+        } else {
+            Decimal256::checked_from_ratio(magnitude, aggregate.count).map_err(|e| {
+                ContractError::UnexpectedError {
+                    reason: e.to_string(),
+                }
+            })?
+        };
+        if average.is_zero() {
+            Some(average.to_string())
+        } else {
+            Some(format!("{}{}", sign, average))
+        }
+    };
+
+    Ok(IndexAggregatesResponse {
+        count: aggregate.count,
+        sum,
+        average,
+        min,
+        max,
+    })
+}
+
+/// Read the min or max value of a numeric custom index within a partition
+/// from the first entry of the index in the given order.
+fn load_partition_extreme(
+    store: &dyn Storage,
+    index_name: &String,
+    index_type: &IndexType,
+    partition: PartitionID,
+    order: Order,
+) -> Result<Option<IndexValue>, ContractError> {
+    Ok(match index_type {
+        IndexType::Int32 => {
+            first_value::<i32>(store, index_name, partition, order)?.map(IndexValue::Int32)
+        },
+        IndexType::Uint8 => {
+            first_value::<u8>(store, index_name, partition, order)?.map(IndexValue::Uint8)
+        },
+        IndexType::Uint16 => {
+            first_value::<u16>(store, index_name, partition, order)?.map(IndexValue::Uint16)
+        },
+        IndexType::Uint32 => {
+            first_value::<u32>(store, index_name, partition, order)?.map(IndexValue::Uint32)
+        },
+        IndexType::Uint64 => first_value::<u64>(store, index_name, partition, order)?
+            .map(|v| IndexValue::Uint64(Uint64::from(v))),
+        IndexType::Uint128 => first_value::<u128>(store, index_name, partition, order)?
+            .map(|v| IndexValue::Uint128(Uint128::from(v))),
+        IndexType::Int64 => {
+            first_value::<i64>(store, index_name, partition, order)?.map(IndexValue::Int64)
+        },
+        IndexType::Int128 => {
+            first_value::<i128>(store, index_name, partition, order)?.map(IndexValue::Int128)
+        },
+        IndexType::Decimal => first_value::<u128>(store, index_name, partition, order)?
+            .map(|v| IndexValue::Decimal(Decimal::raw(v))),
+        IndexType::Decimal256 => {
+            match first_value::<Vec<u8>>(store, index_name, partition, order)? {
+                Some(bytes) => {
+                    let atomics: [u8; 32] = bytes.as_slice().try_into().map_err(|_| {
+                        ContractError::UnexpectedError {
+                            reason: format!("invalid key in index {}", index_name),
+                        }
+                    })?;
+                    Some(IndexValue::Decimal256(Decimal256::new(
+                        Uint256::from_be_bytes(atomics),
+                    )))
+                },
+                None => None,
+            }
+        },
+        _ => None,
+    })
+}
+
+fn first_value<T>(
+    store: &dyn Storage,
+    index_name: &String,
+    partition: PartitionID,
+    order: Order,
+) -> StdResult<Option<T::Output>>
+where
+    T: for<'a> PrimaryKey<'a> + for<'a> Prefixer<'a> + KeyDeserialize,
+{
+    let storage_key = build_index_storage_key(index_name);
+    let index: CustomIndexMap<T> = Map::new(&storage_key);
+    index
+        .sub_prefix(partition)
+        .keys(store, None, None, order)
+        .next()
+        .map(|result| result.map(|(value, _)| value))
+        .transpose()
+}
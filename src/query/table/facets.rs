@@ -0,0 +1,65 @@
+use crate::error::ContractError;
+use crate::msg::{IndexFacet, IndexFacetsQueryParams, IndexFacetsResponse, IndexValue};
+use crate::state::{INDEX_FACET_COUNTS, INDEX_METADATA};
+use crate::util::trim_padding;
+use cosmwasm_std::{Binary, Deps, Order};
+use cw_storage_plus::Bound;
+
+/// Return each distinct value of a custom index with facets in a partition,
+/// along with the number of contracts that have it, in value order.
+pub fn query_facets(
+    deps: Deps,
+    params: IndexFacetsQueryParams,
+) -> Result<IndexFacetsResponse, ContractError> {
+    let store = deps.storage;
+    let index_name = &params.index;
+    let meta = INDEX_METADATA
+        .may_load(store, index_name.clone())?
+        .ok_or_else(|| ContractError::UnknownIndex {
+            index: index_name.clone(),
+        })?;
+
+    if !meta.facets.unwrap_or(false) {
+        return Err(ContractError::ValidationError {
+            reason: format!("index {} has no facets", index_name),
+        });
+    }
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let order = if params.desc.unwrap_or(false) {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+
+    let start = params
+        .cursor
+        .as_ref()
+        .map(|c| Bound::exclusive(c.as_slice()));
+    let (min, max) = match order {
+        Order::Ascending => (start, None),
+        Order::Descending => (None, start),
+    };
+
+    let mut facets: Vec<IndexFacet> = Vec::with_capacity(limit);
+    let mut cursor: Option<Binary> = None;
+
+    for result in INDEX_FACET_COUNTS
+        .prefix((index_name, params.partition))
+        .range(store, min, max, order)
+        .take(limit)
+    {
+        let (value_key, mut facet) = result?;
+        if let IndexValue::String(s) = &facet.value {
+            facet.value = IndexValue::String(trim_padding(s));
+        }
+        facets.push(facet);
+        cursor = Some(value_key.into());
+    }
+
+    if facets.len() < limit {
+        cursor = None;
+    }
+
+    Ok(IndexFacetsResponse { facets, cursor })
+}
@@ -0,0 +1,11 @@
+use crate::error::ContractError;
+use crate::msg::ManagedIndexingProgress;
+use crate::state::MANAGED_INDEXING_PROGRESS;
+use cosmwasm_std::Deps;
+
+/// Return progress of the latest backfill of the managed status index, if any.
+pub fn query_managed_indexing_status(
+    deps: Deps
+) -> Result<Option<ManagedIndexingProgress>, ContractError> {
+    Ok(MANAGED_INDEXING_PROGRESS.may_load(deps.storage)?)
+}
@@ -1,9 +1,23 @@
+mod aggregates;
+mod compound_indices;
+mod facets;
 mod groups;
 mod indices;
+mod managed_indexing_status;
 mod partitions;
+mod predict_address;
+mod reindex_status;
+mod reply_jobs;
 mod tags;
 
+pub use aggregates::query_aggregates as aggregates;
+pub use compound_indices::query_compound_indices as compound_indices;
+pub use facets::query_facets as facets;
 pub use groups::query_groups as groups;
 pub use indices::query_indices as indices;
+pub use managed_indexing_status::query_managed_indexing_status as managed_indexing_status;
 pub use partitions::query_partitions as partitions;
+pub use predict_address::query_predict_address as predict_address;
+pub use reindex_status::query_reindex_status as reindex_status;
+pub use reply_jobs::query_reply_jobs as reply_jobs;
 pub use tags::query_tags as tags;
@@ -0,0 +1,21 @@
+use crate::error::ContractError;
+use crate::msg::{AddressPredictionParams, PredictedAddressResponse};
+use crate::state::{exists_contract_address, predict_contract_address};
+use cosmwasm_std::{Deps, Env};
+
+/// Compute the address of a contract created by the table with the given code
+/// ID and instantiate2 salt, and whether it's already in use.
+pub fn query_predict_address(
+    deps: Deps,
+    env: Env,
+    params: AddressPredictionParams,
+) -> Result<PredictedAddressResponse, ContractError> {
+    let address = predict_contract_address(
+        deps,
+        &env.contract.address,
+        params.code_id.into(),
+        &params.salt,
+    )?;
+    let exists = exists_contract_address(deps.storage, &address);
+    Ok(PredictedAddressResponse { address, exists })
+}
@@ -0,0 +1,12 @@
+use crate::error::ContractError;
+use crate::msg::ReindexProgress;
+use crate::state::REINDEX_PROGRESS;
+use cosmwasm_std::Deps;
+
+/// Return progress of the latest reindex of the given index, if any.
+pub fn query_reindex_status(
+    deps: Deps,
+    index_name: String,
+) -> Result<Option<ReindexProgress>, ContractError> {
+    Ok(REINDEX_PROGRESS.may_load(deps.storage, &index_name)?)
+}
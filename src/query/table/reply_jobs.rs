@@ -0,0 +1,66 @@
+use std::marker::PhantomData;
+
+use crate::error::ContractError;
+use crate::msg::{ReplyJobStatus, ReplyJobView, ReplyJobsResponse, TableReplyJobsQueryParams};
+use crate::state::FAILED_REPLY_JOBS;
+use cosmwasm_std::{Deps, Order};
+use cw_storage_plus::Bound;
+
+/// Return reply jobs with the given status. Jobs are removed when replied to,
+/// and only the failures of items in best-effort batches are kept, so this
+/// lists batch failures only. A single Create that fails reverts instead, and
+/// leaves no job behind.
+pub fn query_reply_jobs(
+    deps: Deps,
+    params: TableReplyJobsQueryParams,
+) -> Result<ReplyJobsResponse, ContractError> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as usize;
+    let desc = params.desc.unwrap_or(false);
+    let order = if desc {
+        Order::Descending
+    } else {
+        Order::Ascending
+    };
+    let (min, max) = match order {
+        Order::Ascending => (
+            params
+                .cursor
+                .and_then(|start| Some(Bound::Exclusive((start.u64(), PhantomData)))),
+            None,
+        ),
+        Order::Descending => (
+            None,
+            params
+                .cursor
+                .and_then(|start| Some(Bound::Exclusive((start.u64(), PhantomData)))),
+        ),
+    };
+
+    let mut jobs: Vec<ReplyJobView> = Vec::with_capacity(4);
+
+    match params.status {
+        ReplyJobStatus::Failed => {
+            for result in FAILED_REPLY_JOBS
+                .range(deps.storage, min, max, order)
+                .take(limit)
+            {
+                let (id, failed) = result?;
+                jobs.push(ReplyJobView {
+                    id: id.into(),
+                    status: ReplyJobStatus::Failed,
+                    job: failed.job,
+                    reason: Some(failed.reason),
+                    failed_at: Some(failed.failed_at),
+                });
+            }
+        },
+    }
+
+    let cursor = if jobs.len() == limit {
+        jobs.last().and_then(|view| Some(view.id))
+    } else {
+        None
+    };
+
+    Ok(ReplyJobsResponse { jobs, cursor })
+}
@@ -1,20 +1,27 @@
+use std::marker::PhantomData;
+
 use crate::context::Context;
 use crate::models::{
-    ContractMetadataView, ContractMetadataViewDetails, Details, DynamicContractMetadata, ReplyJob,
+    ContractMetadataView, ContractMetadataViewDetails, ContractTombstone, CreateBatch, Details,
+    DynamicContractMetadata, FailedReplyJob, IndexAggregate, ReplyJob,
 };
 use crate::msg::{
-    Config, ContractRecord, GroupCreationParams, GroupMetadata, IndexCreationParams, IndexMetadata,
-    IndexType, InstantiateMsg, PartitionCreationParams, PartitionMetadata, PartitionSelector,
-    TableInfo,
+    CompoundIndexCreationParams, CompoundIndexMetadata, Config, ContractDrift, ContractRecord,
+    ContractSelector, ExpirySettings, GeoPoint, GroupCreationParams, GroupMetadata,
+    IndexCreationParams, IndexFacet, IndexMetadata, IndexType, IndexValue, InstantiateMsg,
+    ManagedIndexingProgress, PartitionCreationParams, PartitionMetadata, PartitionSelector,
+    ReindexProgress, StringIndexSettings, StringOverflowPolicy, TableInfo,
 };
 use crate::{error::ContractError, models::ContractMetadata};
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, DepsMut, Env, MessageInfo, Order, Storage, Timestamp, Uint128,
-    Uint64,
+    instantiate2_address, to_json_binary, Addr, Binary, BlockInfo, CodeInfoResponse,
+    ContractInfoResponse, Decimal, Decimal256, Deps, DepsMut, Env, MessageInfo, Order, StdResult,
+    Storage, Timestamp, Uint128, Uint64, WasmQuery,
 };
 use cw_acl::client::Acl;
 use cw_lib::models::Owner;
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Bound, Item, Map};
+use cw_utils::Expiration;
 
 // TODO: store size of each partition Map<u16, Uint64>
 // TODO: add str prefix to custom index names
@@ -24,6 +31,10 @@ pub type GroupID = u32;
 pub type ContractID = u64;
 pub type IndexMap<K> = Map<'static, K, u8>;
 pub type CustomIndexMap<'a, T> = Map<'a, (PartitionID, T, ContractID), u8>;
+pub type CompoundIndexMap<'a> = Map<'a, (PartitionID, Vec<u8>), u8>;
+pub type TextIndexMap<'a> = Map<'a, (PartitionID, &'a str, ContractID), u8>;
+
+pub const MAX_COMPOUND_INDEX_COMPONENTS: usize = 8;
 
 // Marker/dummy value for IndexMap values
 pub const X: u8 = 1;
@@ -53,6 +64,15 @@ pub const CONTRACT_METADATA: Map<ContractID, ContractMetadata> = Map::new("contr
 pub const CONTRACT_DYN_METADATA: Map<ContractID, DynamicContractMetadata> =
     Map::new("contract_dyn_meta");
 
+// Data of archived contracts, kept so that they can be restored
+pub const CONTRACT_TOMBSTONES: Map<ContractID, ContractTombstone> = Map::new("contract_tombstones");
+pub const ARCHIVED_ADDR_2_ID: Map<&Addr, Uint64> = Map::new("archived_addr_2_id");
+
+// Expiry settings of contracts, with global indices for finding expired ones
+pub const CONTRACT_EXPIRY: Map<ContractID, ExpirySettings> = Map::new("contract_expiry");
+pub const IX_EXPIRES_AT_HEIGHT: IndexMap<(u64, ContractID)> = Map::new("ix_expires_at_height");
+pub const IX_EXPIRES_AT_TIME: IndexMap<(u64, ContractID)> = Map::new("ix_expires_at_time");
+
 // Flags indicating that a given contract is suspended
 pub const CONTRACT_SUSPENSIONS: Map<ContractID, bool> = Map::new("contract_suspensions");
 
@@ -68,6 +88,13 @@ pub const CONTRACT_USES_LIFECYCLE_HOOKS: Map<u64, bool> = Map::new("lifecycle_ho
 pub const REPLY_JOBS: Map<u64, ReplyJob> = Map::new("reply_jobs");
 pub const REPLY_JOB_ID_COUNTER: Item<Uint64> = Item::new("reply_job_id_counter");
 
+// Jobs whose submsg failed without reverting the tx, as in best-effort batches
+pub const FAILED_REPLY_JOBS: Map<u64, FailedReplyJob> = Map::new("failed_reply_jobs");
+
+// Results of batch creation jobs still awaiting replies
+pub const CREATE_BATCHES: Map<u64, CreateBatch> = Map::new("create_batches");
+pub const CREATE_BATCH_ID_COUNTER: Item<u64> = Item::new("create_batch_id_counter");
+
 // Allow list, where the keys are the Code ID's that can be instantiated through
 // the create() API. Only used if the allowlist is enabled through config.
 pub const CODE_ID_ALLOWLIST: IndexMap<u64> = Map::new("code_id_allowlist");
@@ -92,6 +119,32 @@ pub const CONTRACT_INDEX_TYPES: Map<(ContractID, &String), IndexType> =
 // Metadata for custom indices.
 pub const INDEX_METADATA: Map<String, IndexMetadata> = Map::new("index_metadata");
 
+// Names of custom indices that must be given a value on create.
+pub const REQUIRED_INDICES: Map<&String, u8> = Map::new("required_indices");
+
+// Owner of each value claimed in a unique custom index. Values are encoded in
+// the same way as compound index keys and, for indices unique per partition,
+// prefixed with the partition ID.
+pub const INDEX_UNIQUE_VALUES: Map<(&String, Vec<u8>), ContractID> =
+    Map::new("index_unique_values");
+
+// Progress of the latest reindex of each custom or compound index.
+pub const REINDEX_PROGRESS: Map<&String, ReindexProgress> = Map::new("reindex_progress");
+
+// Progress of the latest backfill of the managed status index.
+pub const MANAGED_INDEXING_PROGRESS: Item<ManagedIndexingProgress> =
+    Item::new("managed_indexing_progress");
+
+// Metadata for compound indices, built over several custom indices. Entries in
+// a compound index are keyed by the order-preserving encoding of each
+// component value, followed by the contract ID.
+pub const COMPOUND_INDEX_METADATA: Map<String, CompoundIndexMetadata> =
+    Map::new("compound_index_metadata");
+
+// Lookup table for finding the compound indices that include a custom index,
+// like ("status", "status_deadline") => X.
+pub const INDEX_COMPOUNDS: Map<(&String, &String), u8> = Map::new("index_compounds");
+
 // INDEX_* are built-in index maps owned and managed by this contract.
 pub const IX_CONTRACT_ID: IndexMap<(PartitionID, u64, ContractID)> = Map::new("ix_contract_id");
 pub const IX_CODE_ID: IndexMap<(PartitionID, u64, ContractID)> = Map::new("ix_code_id");
@@ -100,6 +153,7 @@ pub const IX_CREATED_AT: IndexMap<(PartitionID, u64, ContractID)> = Map::new("ix
 pub const IX_UPDATED_AT: IndexMap<(PartitionID, u64, ContractID)> = Map::new("ix_updated");
 pub const IX_UPDATED_BY: IndexMap<(PartitionID, String, ContractID)> = Map::new("ix_updated_by");
 pub const IX_REV: IndexMap<(PartitionID, u64, ContractID)> = Map::new("ix_created_by");
+pub const IX_MANAGED: IndexMap<(PartitionID, u8, ContractID)> = Map::new("ix_managed");
 pub const IX_TAG: IndexMap<(PartitionID, &String, ContractID)> = Map::new("ix_tag");
 
 // Groups transcend partitions, i.e. two contracts may belong to the same group
@@ -120,6 +174,44 @@ pub const VALUES_U32: Map<(ContractID, &String), u32> = Map::new("values_u32");
 pub const VALUES_U64: Map<(ContractID, &String), Uint64> = Map::new("values_u64");
 pub const VALUES_U128: Map<(ContractID, &String), Uint128> = Map::new("values_u128");
 pub const VALUES_BINARY: Map<(ContractID, &String), Binary> = Map::new("values_binary");
+pub const VALUES_I64: Map<(ContractID, &String), i64> = Map::new("values_i64");
+pub const VALUES_I128: Map<(ContractID, &String), i128> = Map::new("values_i128");
+pub const VALUES_DECIMAL: Map<(ContractID, &String), Decimal> = Map::new("values_decimal");
+pub const VALUES_DECIMAL256: Map<(ContractID, &String), Decimal256> = Map::new("values_decimal256");
+pub const VALUES_ADDR: Map<(ContractID, &String), Addr> = Map::new("values_addr");
+pub const VALUES_GEO: Map<(ContractID, &String), GeoPoint> = Map::new("values_geo");
+
+// Elements of set-valued indices, keyed by their order-preserving encoding
+pub const VALUES_SET: Map<(ContractID, &String, &[u8]), IndexValue> = Map::new("values_set");
+
+// Running aggregates of each numeric custom index, per partition and group.
+// Values indexed before aggregates were introduced are only included once a
+// Reindex of the index has added them.
+pub const PARTITION_INDEX_AGGREGATES: Map<(&String, PartitionID), IndexAggregate> =
+    Map::new("partition_index_aggregates");
+pub const GROUP_INDEX_AGGREGATES: Map<(&String, GroupID), IndexAggregate> =
+    Map::new("group_index_aggregates");
+
+// Values of numeric custom indices held by the members of each group, keyed by
+// the encoded value followed by the contract ID, from which the min and max of
+// a group are read.
+pub const GROUP_INDEX_VALUES: Map<(&String, GroupID, &[u8]), IndexValue> =
+    Map::new("group_index_values");
+
+// Number of contracts with each distinct value of custom indices with facets,
+// per partition, keyed by the encoded value
+pub const INDEX_FACET_COUNTS: Map<(&String, PartitionID, &[u8]), IndexFacet> =
+    Map::new("index_facet_counts");
+
+// Values of numeric or faceted custom indices counted in the aggregates and
+// facet counts of the partition stored with each, keyed by the encoded value,
+// so that each value is only ever added or removed once.
+pub const INDEX_COUNTED_VALUES: Map<(&String, ContractID, &[u8]), PartitionID> =
+    Map::new("index_counted_values");
+
+// Current encoded key of each contract in each compound index, excluding the
+// trailing contract ID.
+pub const VALUES_COMPOUND: Map<(ContractID, &String), Binary> = Map::new("values_compound");
 
 /// Relationships define an arbitrary M-N named relationship between a contract
 /// ID and an arbitrary Addr, like (contract_id, "winner", user_addr)
@@ -212,7 +304,19 @@ pub fn create_index(
     storage: &mut dyn Storage,
     params: IndexCreationParams,
 ) -> Result<IndexMetadata, ContractError> {
-    INDEX_METADATA.update(
+    if COMPOUND_INDEX_METADATA.has(storage, params.name.clone()) {
+        return Err(ContractError::NotAuthorized {
+            reason: format!("index {} already exists", params.name),
+        });
+    }
+    // Fix the string settings of the index at creation, so that later changes
+    // to the global config don't alter how its existing values are keyed.
+    let strings = if params.index_type == IndexType::String {
+        Some(resolve_string_index_settings(storage, params.strings)?)
+    } else {
+        None
+    };
+    let meta = INDEX_METADATA.update(
         storage,
         params.name.clone(),
         |maybe_meta| -> Result<_, ContractError> {
@@ -225,10 +329,129 @@ pub fn create_index(
                     size: Uint64::zero(),
                     index_type: params.index_type,
                     name: params.name,
+                    constraints: params.constraints,
+                    unique: params.unique,
+                    strings,
+                    multi: params.multi,
+                    facets: params.facets,
+                    text: params.text,
+                    deletion: None,
                 })
             }
         },
-    )
+    )?;
+
+    if meta
+        .constraints
+        .as_ref()
+        .and_then(|c| c.required)
+        .unwrap_or(false)
+    {
+        REQUIRED_INDICES.save(storage, &meta.name, &X)?;
+    }
+
+    Ok(meta)
+}
+
+/// Fill in unset string index settings from the global config.
+pub fn resolve_string_index_settings(
+    storage: &dyn Storage,
+    maybe_settings: Option<StringIndexSettings>,
+) -> Result<StringIndexSettings, ContractError> {
+    let settings = maybe_settings.unwrap_or_default();
+    Ok(StringIndexSettings {
+        case_sensitive: Some(match settings.case_sensitive {
+            Some(case_sensitive) => case_sensitive,
+            None => CONFIG_STR_CASE_SENSITIVE.load(storage)?,
+        }),
+        max_len: Some(match settings.max_len {
+            Some(max_len) => max_len,
+            None => CONFIG_STR_MAX_LEN.load(storage)?,
+        }),
+        overflow: Some(settings.overflow.unwrap_or(StringOverflowPolicy::Reject)),
+    })
+}
+
+/// Load the string settings of a string index, falling back on the global
+/// config for indices created before they had settings of their own.
+pub fn load_string_index_settings(
+    storage: &dyn Storage,
+    index_name: &String,
+) -> Result<StringIndexSettings, ContractError> {
+    let meta = INDEX_METADATA.load(storage, index_name.clone())?;
+    resolve_string_index_settings(storage, meta.strings)
+}
+
+pub fn create_compound_index(
+    storage: &mut dyn Storage,
+    params: CompoundIndexCreationParams,
+) -> Result<CompoundIndexMetadata, ContractError> {
+    let n = params.components.len();
+    if n < 2 || n > MAX_COMPOUND_INDEX_COMPONENTS {
+        return Err(ContractError::ValidationError {
+            reason: format!(
+                "compound index must have between 2 and {} components",
+                MAX_COMPOUND_INDEX_COMPONENTS
+            ),
+        });
+    }
+
+    if INDEX_METADATA.has(storage, params.name.clone()) {
+        return Err(ContractError::NotAuthorized {
+            reason: format!("index {} already exists", params.name),
+        });
+    }
+
+    for (i, component) in params.components.iter().enumerate() {
+        match INDEX_METADATA.may_load(storage, component.clone())? {
+            None => {
+                return Err(ContractError::UnknownIndex {
+                    index: component.clone(),
+                })
+            },
+            Some(meta) if meta.deletion.is_some() => {
+                return Err(ContractError::IndexDeleting {
+                    index: component.clone(),
+                })
+            },
+            Some(meta) if meta.multi.unwrap_or(false) => {
+                return Err(ContractError::ValidationError {
+                    reason: format!("index {} is set-valued", component),
+                })
+            },
+            _ => {},
+        }
+        if params.components[..i].contains(component) {
+            return Err(ContractError::ValidationError {
+                reason: format!("index {} is repeated", component),
+            });
+        }
+    }
+
+    let meta = COMPOUND_INDEX_METADATA.update(
+        storage,
+        params.name.clone(),
+        |maybe_meta| -> Result<_, ContractError> {
+            if maybe_meta.is_some() {
+                Err(ContractError::NotAuthorized {
+                    reason: format!("index {} already exists", params.name),
+                })
+            } else {
+                Ok(CompoundIndexMetadata {
+                    size: Uint64::zero(),
+                    components: params.components.clone(),
+                    name: params.name.clone(),
+                    deletion: None,
+                })
+            }
+        },
+    )?;
+
+    for component in meta.components.iter() {
+        INDEX_COMPOUNDS.save(storage, (component, &meta.name), &X)?;
+    }
+
+    Ok(meta)
 }
 
 pub fn build_contract_metadata_view(
@@ -261,6 +484,7 @@ pub fn build_contract_metadata_view(
                 created_at_height: meta.created_at_height,
                 created_by: meta.created_by,
                 is_managed: meta.is_managed,
+                admin: meta.admin,
                 updated_at_height,
                 updated_by,
                 id: id.into(),
@@ -363,6 +587,29 @@ pub fn load_reply_job(
     }
 }
 
+/// Allocate a new batch ID and save an empty batch of the given size.
+pub fn create_batch(
+    storage: &mut dyn Storage,
+    best_effort: bool,
+    size: u32,
+) -> Result<u64, ContractError> {
+    let batch_id = CREATE_BATCH_ID_COUNTER
+        .may_load(storage)?
+        .unwrap_or_default()
+        + 1;
+    CREATE_BATCH_ID_COUNTER.save(storage, &batch_id)?;
+    CREATE_BATCHES.save(
+        storage,
+        batch_id,
+        &CreateBatch {
+            best_effort,
+            size,
+            results: vec![],
+        },
+    )?;
+    Ok(batch_id)
+}
+
 pub fn load_contract_addr(
     storage: &dyn Storage,
     contract_id: ContractID,
@@ -389,6 +636,47 @@ pub fn load_contract_id(
     }
 }
 
+/// Load a contract's stored value for the given custom index.
+pub fn load_index_value(
+    storage: &dyn Storage,
+    contract_id: ContractID,
+    index_name: &String,
+    index_type: &IndexType,
+) -> Result<IndexValue, ContractError> {
+    let key = (contract_id, index_name);
+    Ok(match index_type {
+        IndexType::String => IndexValue::String(VALUES_STRING.load(storage, key)?),
+        IndexType::Bool => IndexValue::Bool(VALUES_BOOL.load(storage, key)?),
+        IndexType::Timestamp => IndexValue::Timestamp(VALUES_TIME.load(storage, key)?),
+        IndexType::Int32 => IndexValue::Int32(VALUES_I32.load(storage, key)?),
+        IndexType::Uint8 => IndexValue::Uint8(VALUES_U8.load(storage, key)?),
+        IndexType::Uint16 => IndexValue::Uint16(VALUES_U16.load(storage, key)?),
+        IndexType::Uint32 => IndexValue::Uint32(VALUES_U32.load(storage, key)?),
+        IndexType::Uint64 => IndexValue::Uint64(VALUES_U64.load(storage, key)?),
+        IndexType::Uint128 => IndexValue::Uint128(VALUES_U128.load(storage, key)?),
+        IndexType::Binary => IndexValue::Binary(VALUES_BINARY.load(storage, key)?),
+        IndexType::Int64 => IndexValue::Int64(VALUES_I64.load(storage, key)?),
+        IndexType::Int128 => IndexValue::Int128(VALUES_I128.load(storage, key)?),
+        IndexType::Decimal => IndexValue::Decimal(VALUES_DECIMAL.load(storage, key)?),
+        IndexType::Decimal256 => IndexValue::Decimal256(VALUES_DECIMAL256.load(storage, key)?),
+        IndexType::Addr => IndexValue::Addr(VALUES_ADDR.load(storage, key)?),
+        IndexType::Geo => IndexValue::Geo(VALUES_GEO.load(storage, key)?),
+    })
+}
+
+/// Load the elements of a contract's stored set for the given set-valued index.
+pub fn load_index_set_values(
+    storage: &dyn Storage,
+    contract_id: ContractID,
+    index_name: &String,
+) -> Result<Vec<IndexValue>, ContractError> {
+    Ok(VALUES_SET
+        .prefix((contract_id, index_name))
+        .range(storage, None, None, Order::Ascending)
+        .map(|result| result.map(|(_, value)| value))
+        .collect::<StdResult<Vec<_>>>()?)
+}
+
 pub fn load_next_contract_id(
     storage: &mut dyn Storage,
     contract_addr: &Addr,
@@ -412,6 +700,27 @@ pub fn load_next_contract_id(
     Ok(contract_id)
 }
 
+/// Compute the address of a contract instantiated by the table through
+/// instantiate2 with the given code ID and salt.
+pub fn predict_contract_address(
+    deps: Deps,
+    table_addr: &Addr,
+    code_id: u64,
+    salt: &Binary,
+) -> Result<Addr, ContractError> {
+    let code_info: CodeInfoResponse = deps
+        .querier
+        .query(&WasmQuery::CodeInfo { code_id }.into())?;
+    let creator = deps.api.addr_canonicalize(table_addr.as_str())?;
+    let canonical_addr =
+        instantiate2_address(code_info.checksum.as_slice(), &creator, salt.as_slice()).map_err(
+            |e| ContractError::ValidationError {
+                reason: format!("cannot compute instantiate2 address: {}", e),
+            },
+        )?;
+    Ok(deps.api.addr_humanize(&canonical_addr)?)
+}
+
 pub fn create_relationship(
     storage: &mut dyn Storage,
     contract_id: ContractID,
@@ -698,3 +1007,171 @@ pub fn exists_contract_address(
 ) -> bool {
     CONTRACT_ADDR_2_ID.has(storage, addr)
 }
+
+/// Query the code ID and admin of a contract from the chain.
+pub fn query_contract_info(
+    deps: Deps,
+    contract_addr: &Addr,
+) -> Result<ContractInfoResponse, ContractError> {
+    Ok(deps.querier.query(
+        &WasmQuery::ContractInfo {
+            contract_addr: contract_addr.to_string(),
+        }
+        .into(),
+    )?)
+}
+
+/// Load a page of IDs of contracts matching the selector, in ascending order of
+/// contract ID and starting after the given cursor.
+pub fn select_contract_ids(
+    storage: &dyn Storage,
+    selector: &ContractSelector,
+    cursor: Option<ContractID>,
+    limit: usize,
+) -> Result<Vec<ContractID>, ContractError> {
+    Ok(match selector {
+        ContractSelector::CodeId(p, code_id) => IX_CODE_ID
+            .prefix((*p, code_id.u64()))
+            .keys(
+                storage,
+                cursor.and_then(|id| Some(Bound::Exclusive((id, PhantomData)))),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?,
+        ContractSelector::Partition(p) => IX_CONTRACT_ID
+            .sub_prefix(*p)
+            .keys(
+                storage,
+                cursor.and_then(|id| Some(Bound::Exclusive(((id, id), PhantomData)))),
+                None,
+                Order::Ascending,
+            )
+            .map(|r| r.map(|(id, _)| id))
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?,
+        ContractSelector::Group(group_id) => IX_GROUP
+            .prefix(*group_id)
+            .keys(
+                storage,
+                cursor.and_then(|id| Some(Bound::Exclusive((id, PhantomData)))),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?,
+        ContractSelector::Addresses(addrs) => {
+            let mut ids: Vec<ContractID> = Vec::with_capacity(addrs.len());
+            for addr in addrs.iter() {
+                if let Some(id) = CONTRACT_ADDR_2_ID.may_load(storage, addr)? {
+                    if cursor.map(|c| id.u64() > c).unwrap_or(true) {
+                        ids.push(id.into());
+                    }
+                }
+            }
+            ids.sort();
+            ids.dedup();
+            ids.truncate(limit);
+            ids
+        },
+    })
+}
+
+/// Compare a contract's stored metadata against its on-chain contract info,
+/// returning the differences, if any.
+pub fn detect_contract_drift(
+    deps: Deps,
+    table_addr: &Addr,
+    contract_id: ContractID,
+) -> Result<Option<ContractDrift>, ContractError> {
+    let meta = CONTRACT_METADATA.load(deps.storage, contract_id)?;
+    let contract_addr = load_contract_addr(deps.storage, contract_id)?;
+    let info = query_contract_info(deps, &contract_addr)?;
+    let admin = info.admin.map(Addr::unchecked);
+    let is_managed = admin.as_ref() == Some(table_addr);
+
+    if meta.code_id.u64() == info.code_id && meta.is_managed == is_managed && meta.admin == admin {
+        return Ok(None);
+    }
+
+    Ok(Some(ContractDrift {
+        address: contract_addr,
+        id: contract_id.into(),
+        stored_code_id: meta.code_id,
+        code_id: info.code_id.into(),
+        stored_admin: meta.admin,
+        admin,
+        is_managed,
+    }))
+}
+
+/// Replace a contract's expiry settings, moving it within the expiry indices.
+/// Settings of None or Expiration::Never clear any existing expiry.
+pub fn set_contract_expiry(
+    storage: &mut dyn Storage,
+    contract_id: ContractID,
+    maybe_expiry: Option<ExpirySettings>,
+) -> Result<(), ContractError> {
+    if let Some(prev_expiry) = CONTRACT_EXPIRY.may_load(storage, contract_id)? {
+        match prev_expiry.expires {
+            Expiration::AtHeight(h) => IX_EXPIRES_AT_HEIGHT.remove(storage, (h, contract_id)),
+            Expiration::AtTime(t) => IX_EXPIRES_AT_TIME.remove(storage, (t.nanos(), contract_id)),
+            Expiration::Never {} => {},
+        }
+        CONTRACT_EXPIRY.remove(storage, contract_id);
+    }
+
+    if let Some(expiry) = maybe_expiry {
+        match expiry.expires {
+            Expiration::AtHeight(h) => IX_EXPIRES_AT_HEIGHT.save(storage, (h, contract_id), &X)?,
+            Expiration::AtTime(t) => {
+                IX_EXPIRES_AT_TIME.save(storage, (t.nanos(), contract_id), &X)?
+            },
+            Expiration::Never {} => return Ok(()),
+        }
+        CONTRACT_EXPIRY.save(storage, contract_id, &expiry)?;
+    }
+
+    Ok(())
+}
+
+pub fn is_expired(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    contract_id: ContractID,
+) -> Result<bool, ContractError> {
+    Ok(CONTRACT_EXPIRY
+        .may_load(storage, contract_id)?
+        .map(|expiry| expiry.expires.is_expired(block))
+        .unwrap_or(false))
+}
+
+/// Load the IDs of up to `limit` contracts that have expired as of the given
+/// block, oldest first by height and then by time.
+pub fn load_expired_contract_ids(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    limit: usize,
+) -> Result<Vec<ContractID>, ContractError> {
+    let mut ids: Vec<ContractID> = IX_EXPIRES_AT_HEIGHT
+        .keys(storage, None, None, Order::Ascending)
+        .take_while(|r| r.as_ref().map(|(h, _)| *h <= block.height).unwrap_or(true))
+        .take(limit)
+        .map(|r| r.map(|(_, id)| id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if ids.len() < limit {
+        let now = block.time.nanos();
+        for result in IX_EXPIRES_AT_TIME
+            .keys(storage, None, None, Order::Ascending)
+            .take_while(|r| r.as_ref().map(|(t, _)| *t <= now).unwrap_or(true))
+            .take(limit - ids.len())
+        {
+            let (_, id) = result?;
+            ids.push(id);
+        }
+    }
+
+    Ok(ids)
+}
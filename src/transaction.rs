@@ -0,0 +1,208 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::Bound;
+
+use cosmwasm_std::{Order, Record, Storage};
+
+type PendingWrite<'a> = (&'a Vec<u8>, &'a Option<Vec<u8>>);
+
+/// A layer of pending writes over another store. Reads see the pending writes,
+/// which are only applied to the underlying store on commit, so that a series
+/// of writes can be discarded as a whole if any step fails.
+pub struct StorageTransaction<'a> {
+    storage: &'a mut dyn Storage,
+    // Pending writes, with None for removed keys
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> StorageTransaction<'a> {
+    pub fn new(storage: &'a mut dyn Storage) -> Self {
+        Self {
+            storage,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// Apply the pending writes to the underlying store.
+    pub fn commit(self) {
+        for (key, maybe_value) in self.writes.into_iter() {
+            match maybe_value {
+                Some(value) => self.storage.set(&key, &value),
+                None => self.storage.remove(&key),
+            }
+        }
+    }
+}
+
+impl Storage for StorageTransaction<'_> {
+    fn get(
+        &self,
+        key: &[u8],
+    ) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(maybe_value) => maybe_value.clone(),
+            None => self.storage.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Box::new(std::iter::empty());
+            }
+        }
+        let bounds = (
+            start.map_or(Bound::Unbounded, |k| Bound::Included(k.to_vec())),
+            end.map_or(Bound::Unbounded, |k| Bound::Excluded(k.to_vec())),
+        );
+        let writes = self.writes.range::<Vec<u8>, _>(bounds);
+        let writes: Box<dyn Iterator<Item = PendingWrite<'b>> + 'b> = match order {
+            Order::Ascending => Box::new(writes),
+            Order::Descending => Box::new(writes.rev()),
+        };
+        Box::new(MergedRange {
+            base: self.storage.range(start, end, order).peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn set(
+        &mut self,
+        key: &[u8],
+        value: &[u8],
+    ) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(
+        &mut self,
+        key: &[u8],
+    ) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+/// Records of the underlying store merged with pending writes, in key order.
+/// Pending writes shadow records with the same key, and removals hide them.
+struct MergedRange<'a> {
+    base: Peekable<Box<dyn Iterator<Item = Record> + 'a>>,
+    writes: Peekable<Box<dyn Iterator<Item = PendingWrite<'a>> + 'a>>,
+    order: Order,
+}
+
+impl Iterator for MergedRange<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            // None if both sides have the same key
+            let is_base_next = match (self.base.peek(), self.writes.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Some(true),
+                (None, Some(_)) => Some(false),
+                (Some((base_key, _)), Some((write_key, _))) => {
+                    match (base_key.as_slice().cmp(write_key.as_slice()), self.order) {
+                        (Ordering::Equal, _) => None,
+                        (Ordering::Less, Order::Ascending) => Some(true),
+                        (Ordering::Greater, Order::Descending) => Some(true),
+                        _ => Some(false),
+                    }
+                },
+            };
+            match is_base_next {
+                Some(true) => return self.base.next(),
+                Some(false) => {},
+                None => {
+                    self.base.next();
+                },
+            }
+            if let Some((key, Some(value))) = self.writes.next() {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::MemoryStorage;
+
+    fn keys(
+        storage: &dyn Storage,
+        order: Order,
+    ) -> Vec<Vec<u8>> {
+        storage.range(None, None, order).map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn reads_see_pending_writes() {
+        let mut storage = MemoryStorage::new();
+        storage.set(b"a", b"1");
+        storage.set(b"b", b"2");
+
+        let mut tx = StorageTransaction::new(&mut storage);
+        tx.set(b"a", b"3");
+        tx.remove(b"b");
+        tx.set(b"c", b"4");
+
+        assert_eq!(tx.get(b"a"), Some(b"3".to_vec()));
+        assert_eq!(tx.get(b"b"), None);
+        assert_eq!(tx.get(b"c"), Some(b"4".to_vec()));
+    }
+
+    #[test]
+    fn ranges_merge_pending_writes() {
+        let mut storage = MemoryStorage::new();
+        for k in [b"a", b"c", b"e"] {
+            storage.set(k, b"base");
+        }
+
+        let mut tx = StorageTransaction::new(&mut storage);
+        tx.set(b"b", b"new");
+        tx.set(b"c", b"new");
+        tx.remove(b"e");
+        tx.set(b"f", b"new");
+
+        assert_eq!(
+            keys(&tx, Order::Ascending),
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"f".to_vec()]
+        );
+        assert_eq!(
+            keys(&tx, Order::Descending),
+            vec![b"f".to_vec(), b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]
+        );
+        let values: Vec<Vec<u8>> = tx
+            .range(Some(b"b"), Some(b"f"), Order::Ascending)
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(values, vec![b"new".to_vec(), b"new".to_vec()]);
+    }
+
+    #[test]
+    fn writes_apply_only_on_commit() {
+        let mut storage = MemoryStorage::new();
+        storage.set(b"a", b"1");
+
+        let mut tx = StorageTransaction::new(&mut storage);
+        tx.remove(b"a");
+        tx.set(b"b", b"2");
+        drop(tx);
+        assert_eq!(storage.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(storage.get(b"b"), None);
+
+        let mut tx = StorageTransaction::new(&mut storage);
+        tx.remove(b"a");
+        tx.set(b"b", b"2");
+        tx.commit();
+        assert_eq!(storage.get(b"a"), None);
+        assert_eq!(storage.get(b"b"), Some(b"2".to_vec()));
+    }
+}
@@ -1,6 +1,11 @@
-use std::str::FromStr;
+use std::{collections::BTreeSet, str::FromStr};
+
+use cosmwasm_std::{Addr, Binary, Timestamp};
 
 use crate::error::ContractError;
+use crate::msg::{
+    GeoArea, GeoPoint, IndexType, IndexValue, StringIndexSettings, StringOverflowPolicy,
+};
 
 pub fn parse<T: FromStr>(v_str: String) -> Result<T, ContractError> {
     match v_str.parse::<T>() {
@@ -25,6 +30,59 @@ pub fn build_index_storage_key(name: &String) -> String {
     format!("_ix_{}", name)
 }
 
+pub fn build_text_index_storage_key(name: &String) -> String {
+    format!("_tx_{}", name)
+}
+
+/// Split text into its distinct lower-cased words, i.e. runs of alphanumeric
+/// characters.
+pub fn tokenize_text(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Normalize a value of a string index according to its settings. The case is
+/// folded unless the index is case sensitive, and values longer than the max
+/// length are truncated or rejected. Unless only a prefix is being normalized,
+/// the result is padded to the max length.
+pub fn normalize_index_str(
+    index_name: &String,
+    settings: &StringIndexSettings,
+    value: &str,
+    is_padded: bool,
+) -> Result<String, ContractError> {
+    let mut value = if settings.case_sensitive.unwrap_or(false) {
+        value.to_owned()
+    } else {
+        value.to_lowercase()
+    };
+    if let Some(max_len) = settings.max_len.map(|n| n as usize) {
+        if value.len() > max_len {
+            match settings.overflow {
+                Some(StringOverflowPolicy::Truncate) => {
+                    let mut end = max_len;
+                    while !value.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    value.truncate(end);
+                },
+                _ => {
+                    return Err(ContractError::IndexConstraintViolation {
+                        index: index_name.clone(),
+                        reason: format!("length is greater than {} bytes", max_len),
+                    })
+                },
+            }
+        }
+        if is_padded {
+            value = pad(&value, max_len);
+        }
+    }
+    Ok(value)
+}
+
 pub fn pad(
     input: &str,
     target_length: usize,
@@ -39,3 +97,637 @@ pub fn pad(
 pub fn trim_padding(input: &String) -> String {
     input.trim_end_matches('\0').to_string()
 }
+
+/// Return true for index types with running aggregates.
+pub fn is_numeric_index_type(index_type: &IndexType) -> bool {
+    matches!(
+        index_type,
+        IndexType::Int32
+            | IndexType::Uint8
+            | IndexType::Uint16
+            | IndexType::Uint32
+            | IndexType::Uint64
+            | IndexType::Uint128
+            | IndexType::Int64
+            | IndexType::Int128
+            | IndexType::Decimal
+            | IndexType::Decimal256
+    )
+}
+
+/// Parse a raw query string as a value of the given index type, using the same
+/// string formats as range queries on single custom indices.
+pub fn parse_index_value(
+    index_type: &IndexType,
+    s: String,
+) -> Result<IndexValue, ContractError> {
+    Ok(match index_type {
+        IndexType::String => IndexValue::String(s),
+        IndexType::Bool => IndexValue::Bool(parse_bool(s)? != 0),
+        IndexType::Timestamp => IndexValue::Timestamp(Timestamp::from_nanos(parse(s)?)),
+        IndexType::Int32 => IndexValue::Int32(parse(s)?),
+        IndexType::Uint8 => IndexValue::Uint8(parse(s)?),
+        IndexType::Uint16 => IndexValue::Uint16(parse(s)?),
+        IndexType::Uint32 => IndexValue::Uint32(parse(s)?),
+        IndexType::Uint64 => IndexValue::Uint64(parse(s)?),
+        IndexType::Uint128 => IndexValue::Uint128(parse(s)?),
+        IndexType::Binary => IndexValue::Binary(Binary::from_base64(&s)?),
+        IndexType::Int64 => IndexValue::Int64(parse(s)?),
+        IndexType::Int128 => IndexValue::Int128(parse(s)?),
+        IndexType::Decimal => IndexValue::Decimal(parse(s)?),
+        IndexType::Decimal256 => IndexValue::Decimal256(parse(s)?),
+        IndexType::Addr => IndexValue::Addr(Addr::unchecked(s)),
+        IndexType::Geo => IndexValue::Geo(parse_geo_point(s)?),
+    })
+}
+
+/// Encode a value so that comparing encodings byte-wise gives the same order
+/// as comparing the values themselves. Variable-length values are escaped and
+/// terminated, so encodings can be concatenated to form compound keys.
+pub fn encode_ordered(value: &IndexValue) -> Vec<u8> {
+    match value {
+        IndexValue::String(v) => encode_ordered_bytes(trim_padding(v).as_bytes()),
+        IndexValue::Bool(v) => vec![*v as u8],
+        IndexValue::Timestamp(v) => v.nanos().to_be_bytes().to_vec(),
+        IndexValue::Int32(v) => ((*v as u32) ^ (1 << 31)).to_be_bytes().to_vec(),
+        IndexValue::Uint8(v) => vec![*v],
+        IndexValue::Uint16(v) => v.to_be_bytes().to_vec(),
+        IndexValue::Uint32(v) => v.to_be_bytes().to_vec(),
+        IndexValue::Uint64(v) => v.u64().to_be_bytes().to_vec(),
+        IndexValue::Uint128(v) => v.u128().to_be_bytes().to_vec(),
+        IndexValue::Binary(v) => encode_ordered_bytes(v.as_slice()),
+        IndexValue::Int64(v) => ((*v as u64) ^ (1 << 63)).to_be_bytes().to_vec(),
+        IndexValue::Int128(v) => ((*v as u128) ^ (1 << 127)).to_be_bytes().to_vec(),
+        IndexValue::Decimal(v) => v.atomics().u128().to_be_bytes().to_vec(),
+        IndexValue::Decimal256(v) => v.atomics().to_be_bytes().to_vec(),
+        IndexValue::Addr(v) => encode_ordered_bytes(v.as_bytes()),
+        IndexValue::Geo(v) => {
+            // The geohash, followed by the exact coordinates
+            let mut encoded = geohash(v, GEOHASH_PRECISION).into_bytes();
+            encoded.extend(((v.lat as u32) ^ (1 << 31)).to_be_bytes());
+            encoded.extend(((v.lon as u32) ^ (1 << 31)).to_be_bytes());
+            encoded
+        },
+    }
+}
+
+fn encode_ordered_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(bytes.len() + 2);
+    for b in bytes.iter() {
+        encoded.push(*b);
+        if *b == 0 {
+            encoded.push(0xff);
+        }
+    }
+    encoded.extend_from_slice(&[0, 1]);
+    encoded
+}
+
+/// Return the smallest byte string greater than every byte string beginning
+/// with the given prefix, or None if there is no such bound.
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = prefix.to_vec();
+    while let Some(b) = bytes.pop() {
+        if b < u8::MAX {
+            bytes.push(b + 1);
+            return Some(bytes);
+        }
+    }
+    None
+}
+
+/// Match a string against a glob pattern, where '*' matches any sequence of
+/// characters and '?' matches any single character.
+pub fn glob_match(
+    pattern: &str,
+    s: &str,
+) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    let (mut i, mut j) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while j < s.len() {
+        if i < pattern.len() && (pattern[i] == '?' || pattern[i] == s[j]) {
+            i += 1;
+            j += 1;
+        } else if i < pattern.len() && pattern[i] == '*' {
+            backtrack = Some((i, j));
+            i += 1;
+        } else if let Some((star_i, star_j)) = backtrack {
+            // Let the last '*' absorb one more character and retry
+            backtrack = Some((star_i, star_j + 1));
+            i = star_i + 1;
+            j = star_j + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[i..].iter().all(|c| *c == '*')
+}
+
+/// Number of characters in the geohashes stored in geo indices, i.e. cells of
+/// about 3.7cm by 1.9cm at the equator
+pub const GEOHASH_PRECISION: usize = 12;
+
+/// Max radius of a geo query, in meters
+pub const MAX_GEO_RADIUS: u32 = 1_000_000;
+
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+const MICRODEGREES: i64 = 1_000_000;
+const MAX_LAT: i64 = 90 * MICRODEGREES;
+const MAX_LON: i64 = 180 * MICRODEGREES;
+const METERS_PER_DEGREE: i64 = 111_195;
+
+// Cosine of each whole degree from 0 to 90, times one million
+const COS_TABLE: [i64; 91] = [
+    1000000, 999848, 999391, 998630, 997564, 996195, 994522, 992546, 990268, 987688, 984808,
+    981627, 978148, 974370, 970296, 965926, 961262, 956305, 951057, 945519, 939693, 933580, 927184,
+    920505, 913545, 906308, 898794, 891007, 882948, 874620, 866025, 857167, 848048, 838671, 829038,
+    819152, 809017, 798636, 788011, 777146, 766044, 754710, 743145, 731354, 719340, 707107, 694658,
+    681998, 669131, 656059, 642788, 629320, 615661, 601815, 587785, 573576, 559193, 544639, 529919,
+    515038, 500000, 484810, 469472, 453990, 438371, 422618, 406737, 390731, 374607, 358368, 342020,
+    325568, 309017, 292372, 275637, 258819, 241922, 224951, 207912, 190809, 173648, 156434, 139173,
+    121869, 104528, 87156, 69756, 52336, 34899, 17452, 0,
+];
+
+pub fn validate_geo_point(point: &GeoPoint) -> Result<(), ContractError> {
+    if (point.lat as i64).abs() > MAX_LAT || (point.lon as i64).abs() > MAX_LON {
+        return Err(ContractError::ValidationError {
+            reason: format!("invalid coordinates: {},{}", point.lat, point.lon),
+        });
+    }
+    Ok(())
+}
+
+/// Parse a point given as "lat,lon" in microdegrees.
+pub fn parse_geo_point(s: String) -> Result<GeoPoint, ContractError> {
+    let (lat, lon) = s
+        .split_once(',')
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: format!("cannot parse coordinates: {}", s),
+        })?;
+    let point = GeoPoint {
+        lat: parse(lat.trim().to_owned())?,
+        lon: parse(lon.trim().to_owned())?,
+    };
+    validate_geo_point(&point)?;
+    Ok(point)
+}
+
+pub fn validate_geo_area(area: &GeoArea) -> Result<(), ContractError> {
+    match area {
+        GeoArea::BoundingBox {
+            south_west,
+            north_east,
+        } => {
+            validate_geo_point(south_west)?;
+            validate_geo_point(north_east)?;
+            if south_west.lat > north_east.lat {
+                return Err(ContractError::ValidationError {
+                    reason: "south edge of bounding box is north of its north edge".to_owned(),
+                });
+            }
+        },
+        GeoArea::Radius { center, meters } => {
+            validate_geo_point(center)?;
+            if *meters > MAX_GEO_RADIUS {
+                return Err(ContractError::ValidationError {
+                    reason: format!("radius is greater than {} meters", MAX_GEO_RADIUS),
+                });
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Get the geohash of a point with the given number of characters.
+pub fn geohash(
+    point: &GeoPoint,
+    precision: usize,
+) -> String {
+    let (lon_bits, lat_bits) = geohash_grid_bits(precision);
+    encode_geohash_cell(
+        geohash_grid_position(point.lon as i64, MAX_LON, lon_bits),
+        geohash_grid_position(point.lat as i64, MAX_LAT, lat_bits),
+        precision,
+    )
+}
+
+/// Get the sorted geohash prefixes of the cells that cover an area, using the
+/// longest prefixes for which there are no more than max_cells cells. Single
+/// character prefixes are used for areas too large for that.
+pub fn geohash_cover(
+    area: &GeoArea,
+    max_cells: u64,
+) -> Vec<String> {
+    let boxes = geo_area_boxes(area);
+    let mut precision = GEOHASH_PRECISION;
+    loop {
+        let (lon_bits, lat_bits) = geohash_grid_bits(precision);
+        let ranges: Vec<(u64, u64, u64, u64)> = boxes
+            .iter()
+            .map(|(south, west, north, east)| {
+                (
+                    geohash_grid_position(*west, MAX_LON, lon_bits),
+                    geohash_grid_position(*east, MAX_LON, lon_bits),
+                    geohash_grid_position(*south, MAX_LAT, lat_bits),
+                    geohash_grid_position(*north, MAX_LAT, lat_bits),
+                )
+            })
+            .collect();
+        let n_cells = ranges.iter().fold(0u64, |n, (x0, x1, y0, y1)| {
+            n.saturating_add((x1 - x0 + 1).saturating_mul(y1 - y0 + 1))
+        });
+        if n_cells <= max_cells || precision == 1 {
+            let mut cells: BTreeSet<String> = BTreeSet::new();
+            for (x0, x1, y0, y1) in ranges.into_iter() {
+                for x in x0..=x1 {
+                    for y in y0..=y1 {
+                        cells.insert(encode_geohash_cell(x, y, precision));
+                    }
+                }
+            }
+            return cells.into_iter().collect();
+        }
+        precision -= 1;
+    }
+}
+
+/// Return true if the point lies within the area. Distances from the center
+/// of a radius are approximated by treating the surrounding area as flat,
+/// which is accurate to well within a percent for radii up to MAX_GEO_RADIUS
+/// away from the poles.
+pub fn geo_area_contains(
+    area: &GeoArea,
+    point: &GeoPoint,
+) -> bool {
+    let (lat, lon) = (point.lat as i64, point.lon as i64);
+    match area {
+        GeoArea::BoundingBox {
+            south_west,
+            north_east,
+        } => {
+            let (west, east) = (south_west.lon as i64, north_east.lon as i64);
+            let is_within_lon = if west <= east {
+                west <= lon && lon <= east
+            } else {
+                west <= lon || lon <= east
+            };
+            south_west.lat as i64 <= lat && lat <= north_east.lat as i64 && is_within_lon
+        },
+        GeoArea::Radius { center, meters } => {
+            let radius = radius_microdegrees(*meters) as i128;
+            let d_lat = lat - center.lat as i64;
+            let mut d_lon = lon - center.lon as i64;
+            if d_lon > MAX_LON {
+                d_lon -= 2 * MAX_LON;
+            } else if d_lon < -MAX_LON {
+                d_lon += 2 * MAX_LON;
+            }
+            let d_x = d_lon * cos_microdegrees((lat + center.lat as i64) / 2) / MICRODEGREES;
+            let (d_x, d_y) = (d_x as i128, d_lat as i128);
+            d_x * d_x + d_y * d_y <= radius * radius
+        },
+    }
+}
+
+/// Get the (south, west, north, east) edges of boxes that together cover an
+/// area, none of which cross the antimeridian.
+fn geo_area_boxes(area: &GeoArea) -> Vec<(i64, i64, i64, i64)> {
+    let (south, west, north, east) = match area {
+        GeoArea::BoundingBox {
+            south_west,
+            north_east,
+        } => (
+            south_west.lat as i64,
+            south_west.lon as i64,
+            north_east.lat as i64,
+            north_east.lon as i64,
+        ),
+        GeoArea::Radius { center, meters } => {
+            let radius = radius_microdegrees(*meters);
+            let (lat, lon) = (center.lat as i64, center.lon as i64);
+            let south = (lat - radius).max(-MAX_LAT);
+            let north = (lat + radius).min(MAX_LAT);
+            // Widen the box for the edge nearest a pole, where it's narrowest
+            let cos = cos_microdegrees(south.abs().max(north.abs()));
+            if cos == 0 || radius * MICRODEGREES / cos >= MAX_LON {
+                return vec![(south, -MAX_LON, north, MAX_LON)];
+            }
+            let half_width = radius * MICRODEGREES / cos + 1;
+            let (west, east) = (lon - half_width, lon + half_width);
+            if west < -MAX_LON {
+                (south, west + 2 * MAX_LON, north, east)
+            } else if east > MAX_LON {
+                (south, west, north, east - 2 * MAX_LON)
+            } else {
+                (south, west, north, east)
+            }
+        },
+    };
+    if west <= east {
+        vec![(south, west, north, east)]
+    } else {
+        vec![
+            (south, west, north, MAX_LON),
+            (south, -MAX_LON, north, east),
+        ]
+    }
+}
+
+fn radius_microdegrees(meters: u32) -> i64 {
+    meters as i64 * MICRODEGREES / METERS_PER_DEGREE
+}
+
+/// Get the cosine of a latitude in microdegrees, times one million.
+fn cos_microdegrees(lat: i64) -> i64 {
+    let lat = lat.abs();
+    let degrees = (lat / MICRODEGREES) as usize;
+    if degrees >= 90 {
+        return 0;
+    }
+    let fraction = lat % MICRODEGREES;
+    COS_TABLE[degrees] - (COS_TABLE[degrees] - COS_TABLE[degrees + 1]) * fraction / MICRODEGREES
+}
+
+/// Get the number of bits of longitude and latitude in a geohash with the
+/// given number of characters. Bits alternate, starting with longitude.
+fn geohash_grid_bits(precision: usize) -> (u32, u32) {
+    let n_bits = 5 * precision as u32;
+    ((n_bits + 1) / 2, n_bits / 2)
+}
+
+/// Get the index of the geohash grid cell containing a coordinate in the
+/// range [-max, max], along an axis divided into 2^n_bits cells.
+fn geohash_grid_position(
+    value: i64,
+    max: i64,
+    n_bits: u32,
+) -> u64 {
+    let n_cells = 1u128 << n_bits;
+    let offset = (value + max).clamp(0, 2 * max) as u128;
+    ((offset * n_cells) / (2 * max) as u128).min(n_cells - 1) as u64
+}
+
+fn encode_geohash_cell(
+    x: u64,
+    y: u64,
+    precision: usize,
+) -> String {
+    let (mut lon_bits, mut lat_bits) = geohash_grid_bits(precision);
+    let mut hash = String::with_capacity(precision);
+    let mut char_index: usize = 0;
+    for i in 0..5 * precision {
+        let bit = if i % 2 == 0 {
+            lon_bits -= 1;
+            (x >> lon_bits) & 1
+        } else {
+            lat_bits -= 1;
+            (y >> lat_bits) & 1
+        };
+        char_index = (char_index << 1) | bit as usize;
+        if i % 5 == 4 {
+            hash.push(GEOHASH_ALPHABET[char_index] as char);
+            char_index = 0;
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{Decimal, Uint64};
+
+    fn assert_ordered(values: &[IndexValue]) {
+        for pair in values.windows(2) {
+            assert!(
+                encode_ordered(&pair[0]) < encode_ordered(&pair[1]),
+                "{:?} should sort before {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    fn compound_key(values: &[IndexValue]) -> Vec<u8> {
+        values.iter().flat_map(encode_ordered).collect()
+    }
+
+    #[test]
+    fn encode_ordered_sorts_signed_values() {
+        assert_ordered(&[i32::MIN, -256, -1, 0, 1, 255, i32::MAX].map(IndexValue::Int32));
+        assert_ordered(&[i64::MIN, -1, 0, 1, i64::MAX].map(IndexValue::Int64));
+        assert_ordered(&[i128::MIN, -1, 0, 1, i128::MAX].map(IndexValue::Int128));
+    }
+
+    #[test]
+    fn encode_ordered_sorts_decimals() {
+        let values: Vec<IndexValue> = ["0", "0.000000000000000001", "0.5", "1", "1.25", "10"]
+            .iter()
+            .map(|s| IndexValue::Decimal(Decimal::from_str(s).unwrap()))
+            .collect();
+        assert_ordered(&values);
+    }
+
+    #[test]
+    fn encode_ordered_strings_with_nul_sort_as_compound_prefixes() {
+        // Shorter strings sort first whatever follows them in a compound key
+        let strings = ["a", "a\0\0b", "a\0b", "ab", "b"].map(|s| IndexValue::String(s.to_owned()));
+        let (min, max) = (
+            IndexValue::Uint64(Uint64::zero()),
+            IndexValue::Uint64(Uint64::MAX),
+        );
+        for pair in strings.windows(2) {
+            assert!(
+                compound_key(&[pair[0].clone(), max.clone()])
+                    < compound_key(&[pair[1].clone(), min.clone()]),
+                "{:?} should sort before {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+
+        let binaries = [
+            vec![1],
+            vec![1, 0],
+            vec![1, 0, 0],
+            vec![1, 0, 1],
+            vec![1, 1],
+        ]
+        .map(|b| IndexValue::Binary(Binary::from(b)));
+        for pair in binaries.windows(2) {
+            assert!(
+                compound_key(&[pair[0].clone(), max.clone()])
+                    < compound_key(&[pair[1].clone(), min.clone()]),
+                "{:?} should sort before {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn prefix_successor_bounds_prefix() {
+        assert_eq!(prefix_successor(&[1, 2]), Some(vec![1, 3]));
+        assert_eq!(prefix_successor(&[1, 0xff]), Some(vec![2]));
+        assert_eq!(prefix_successor(&[0xff, 0xff]), None);
+        assert_eq!(prefix_successor(&[]), None);
+
+        let prefix = encode_ordered(&IndexValue::String("ab".to_owned()));
+        let bound = prefix_successor(&prefix).unwrap();
+        let mut key = prefix.clone();
+        key.extend([0xff; 8]);
+        assert!(prefix < key && key < bound);
+    }
+
+    #[test]
+    fn glob_match_backtracks_over_stars() {
+        // The first candidate for each '*' isn't always the one that matches
+        assert!(glob_match("a*bc", "abcbc"));
+        assert!(glob_match("*ab", "aaab"));
+        assert!(glob_match("*a*b*c*", "xxaxxbxxbxxc"));
+        assert!(glob_match("*.txt", "notes.txt.txt"));
+        assert!(!glob_match("a*bc", "abcbd"));
+        assert!(!glob_match("*a*b", "bbba"));
+    }
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("", ""));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("**", "abc"));
+        assert!(!glob_match("", "a"));
+        assert!(!glob_match("?", ""));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "ac"));
+        // '?' matches a single character, not a single byte
+        assert!(glob_match("caf?", "café"));
+        assert!(!glob_match("caf??", "café"));
+    }
+
+    fn string_settings(
+        max_len: u16,
+        overflow: StringOverflowPolicy,
+    ) -> StringIndexSettings {
+        StringIndexSettings {
+            case_sensitive: None,
+            max_len: Some(max_len),
+            overflow: Some(overflow),
+        }
+    }
+
+    #[test]
+    fn normalize_index_str_truncates_at_char_boundaries() {
+        let name = "name".to_owned();
+        let settings = string_settings(4, StringOverflowPolicy::Truncate);
+
+        // "é" takes two bytes, so cutting at four bytes would split it
+        let value = normalize_index_str(&name, &settings, "abcé", false).unwrap();
+        assert_eq!(value, "abc");
+        let value = normalize_index_str(&name, &settings, "abcé", true).unwrap();
+        assert_eq!(value, "abc\0");
+        let value = normalize_index_str(&name, &settings, "€€", true).unwrap();
+        assert_eq!(value, "€\0");
+        let value = normalize_index_str(&name, &settings, "abcd", true).unwrap();
+        assert_eq!(value, "abcd");
+    }
+
+    #[test]
+    fn normalize_index_str_folds_case_and_rejects_overflow() {
+        let name = "name".to_owned();
+        let settings = string_settings(8, StringOverflowPolicy::Reject);
+        let value = normalize_index_str(&name, &settings, "ÀbC", false).unwrap();
+        assert_eq!(value, "àbc");
+
+        let case_sensitive = StringIndexSettings {
+            case_sensitive: Some(true),
+            ..settings.clone()
+        };
+        let value = normalize_index_str(&name, &case_sensitive, "ÀbC", false).unwrap();
+        assert_eq!(value, "ÀbC");
+
+        assert!(matches!(
+            normalize_index_str(&name, &settings, "abcdefghé", false),
+            Err(ContractError::IndexConstraintViolation { .. })
+        ));
+    }
+
+    fn point(
+        lat: i32,
+        lon: i32,
+    ) -> GeoPoint {
+        GeoPoint { lat, lon }
+    }
+
+    fn is_covered(
+        cells: &[String],
+        p: &GeoPoint,
+    ) -> bool {
+        let hash = geohash(p, GEOHASH_PRECISION);
+        cells.iter().any(|cell| hash.starts_with(cell.as_str()))
+    }
+
+    #[test]
+    fn geohash_matches_reference_hashes() {
+        assert_eq!(geohash(&point(57_649_110, 10_407_440), 11), "u4pruydqqvj");
+        assert_eq!(geohash(&point(42_600_000, -5_600_000), 5), "ezs42");
+        assert_eq!(geohash(&point(0, 0), GEOHASH_PRECISION), "s00000000000");
+        assert_eq!(
+            geohash(&point(-90_000_000, -180_000_000), GEOHASH_PRECISION),
+            "000000000000"
+        );
+        assert_eq!(
+            geohash(&point(90_000_000, 180_000_000), GEOHASH_PRECISION),
+            "zzzzzzzzzzzz"
+        );
+    }
+
+    #[test]
+    fn geohash_cover_spans_antimeridian() {
+        let area = GeoArea::BoundingBox {
+            south_west: point(-100_000, 179_900_000),
+            north_east: point(100_000, -179_900_000),
+        };
+        let cells = geohash_cover(&area, 64);
+        assert!(!cells.is_empty() && cells.len() <= 64);
+        assert!(is_covered(&cells, &point(0, 179_950_000)));
+        assert!(is_covered(&cells, &point(0, -179_950_000)));
+        assert!(!is_covered(&cells, &point(0, 0)));
+
+        let area = GeoArea::Radius {
+            center: point(0, 179_999_000),
+            meters: 5000,
+        };
+        let cells = geohash_cover(&area, 64);
+        assert!(is_covered(&cells, &point(0, 179_999_000)));
+        assert!(is_covered(&cells, &point(20_000, -179_980_000)));
+        assert!(geo_area_contains(&area, &point(0, -179_990_000)));
+        assert!(!geo_area_contains(&area, &point(0, -179_900_000)));
+    }
+
+    #[test]
+    fn geo_area_contains_radius_and_boxes() {
+        // One kilometer is about 9000 microdegrees of latitude, and wider in
+        // longitude away from the equator
+        let area = GeoArea::Radius {
+            center: point(51_500_000, 0),
+            meters: 1000,
+        };
+        assert!(geo_area_contains(&area, &point(51_508_900, 0)));
+        assert!(!geo_area_contains(&area, &point(51_509_100, 0)));
+        assert!(geo_area_contains(&area, &point(51_500_000, -14_000)));
+        assert!(!geo_area_contains(&area, &point(51_500_000, -16_000)));
+
+        let area = GeoArea::BoundingBox {
+            south_west: point(-1_000_000, 179_000_000),
+            north_east: point(1_000_000, -179_000_000),
+        };
+        assert!(geo_area_contains(&area, &point(0, 180_000_000)));
+        assert!(geo_area_contains(&area, &point(0, -179_500_000)));
+        assert!(!geo_area_contains(&area, &point(0, 0)));
+        assert!(!geo_area_contains(&area, &point(2_000_000, 179_500_000)));
+    }
+}